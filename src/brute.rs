@@ -0,0 +1,93 @@
+/*!
+
+A brute-force reference solver, for checking the search against. Available with the `testing` feature.
+
+Every entry of $M$ is tried at every value from zero up to what its coefficient divides out of its column, or up to its
+bound if that is less, and at just its value if it is fixed and within those limits. The entries of a row constrained
+to sum to zero are only tried at zero. A matrix is kept if $R \cdot M = C$ and it meets the row bounds, the extension
+row and the limits on the number of nonzero entries of rows. Each column is checked as soon as it is filled, but the
+number of matrices tried still grows exponentially with the number of entries, so this is only for small systems.
+
+*/
+
+use alloc::vec::Vec;
+
+use crate::{DiophantineInt, DiophantineSystem, SystemSpec};
+
+impl<T: DiophantineInt> DiophantineSystem<T> {
+  /// Every solution of the system as it was built, as matrices with rows in insertion order like `solution_matrix()`,
+  /// sorted in ascending order. Found by trying every matrix within bounds rather than by the search, so the result
+  /// is a reference for `solve()`; see the module documentation. Leaves the system untouched.
+  pub fn brute_force_solutions(&self) -> Vec<Vec<Vec<T>>> {
+    let spec          = self.spec();
+    let mut matrix    = vec![vec![T::ZERO; spec.columns.len()]; spec.rows.len()];
+    let mut solutions = Vec::new();
+    let first         = spec.columns.first().copied().unwrap_or(T::ZERO);
+    extend(&spec, 0, 0, first, &mut matrix, &mut solutions);
+    solutions.sort();
+    solutions
+  }
+}
+
+/// Tries every value of the entry at row `r` of column `c`, of which `left` remains to be made up by rows `r..`, and
+/// goes on to the next entry, filling the columns one at a time.
+fn extend<T: DiophantineInt>(
+  spec      : &SystemSpec<T>,
+  r         : usize,
+  c         : usize,
+  left      : T,
+  matrix    : &mut Vec<Vec<T>>,
+  solutions : &mut Vec<Vec<Vec<T>>>,
+) {
+  if c == spec.columns.len() {
+    if meets_row_bounds(spec, matrix) {
+      solutions.push(matrix.clone());
+    }
+    return;
+  }
+  if r == spec.rows.len() {
+    if left == T::ZERO {
+      let next = spec.columns.get(c + 1).copied().unwrap_or(T::ZERO);
+      extend(spec, 0, c + 1, next, matrix, solutions);
+    }
+    return;
+  }
+
+  let coeff = spec.rows[r].coeff;
+  let most  = if spec.zero_rows.contains(&r) { T::ZERO } else { left / coeff };
+  let bound = spec.cell_max
+      .iter()
+      .filter(|&&(row, col, _)| row == r && col == c)
+      .fold(most, |bound, &(_, _, max)| bound.min(max));
+  let (low, high) = match spec.fixed.iter().find(|&&(row, col, _)| row == r && col == c) {
+    Some(&(_, _, value)) => (value, value.min(bound)),
+    None                 => (T::ZERO, bound),
+  };
+  let mut value = low;
+  while value <= high {
+    matrix[r][c] = value;
+    extend(spec, r + 1, c, left - value * coeff, matrix, solutions);
+    if value == high {
+      break;
+    }
+    value += T::ONE;
+  }
+  matrix[r][c] = T::ZERO;
+}
+
+/// Whether every row of `matrix` has a sum within its bounds, as adjusted for the extension row, and no more nonzero
+/// entries than allowed.
+fn meets_row_bounds<T: DiophantineInt>(spec: &SystemSpec<T>, matrix: &[Vec<T>]) -> bool {
+  spec.rows.iter().zip(matrix.iter()).enumerate().all(|(i, (row, entries))| {
+    let sum      = entries.iter().fold(T::ZERO, |sum, &entry| sum + entry);
+    let nonzero  = entries.iter().filter(|&&entry| entry > T::ZERO).count();
+    let min_size = match spec.extension_row {
+      Some(extension) if extension == i => T::ZERO,
+      Some(_)                           => row.min_size.max(T::ONE),
+      None                              => row.min_size,
+    };
+    sum >= min_size
+        && (row.max_size == T::UNBOUNDED || sum <= row.max_size)
+        && spec.cell_count_max.iter().all(|&(r, max)| r != i || nonzero <= max)
+  })
+}
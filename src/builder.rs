@@ -0,0 +1,99 @@
+/*!
+
+A fluent way to construct a `DiophantineSystem` that reports problems as soon as they can be detected.
+
+```rust,no_run
+# use diophantine::{DiophantineError, DiophantineSystemBuilder};
+# fn main() -> Result<(), DiophantineError> {
+let mut system = DiophantineSystemBuilder::new()
+  .row(2, 1, 3)
+  .row(1, 1, 5)
+  .column(3)
+  .column(4)
+  .build()?;
+while system.solve() {
+  println!("{:?}", system.solution_matrix());
+}
+# Ok(())
+# }
+```
+
+*/
+
+use crate::{DiophantineError, DiophantineInt, DiophantineSystem};
+
+/// Builds a `DiophantineSystem` row by row and column by column. Each row and column is validated as it is added, and
+/// the first error is kept and returned by `build()`, which also rejects systems whose row bounds cannot be met by the
+/// column sum.
+#[derive(Clone, Debug)]
+pub struct DiophantineSystemBuilder<T: DiophantineInt = u32> {
+  system     : DiophantineSystem<T>,
+  error      : Option<DiophantineError>, // the first error encountered
+  min_total  : T,    // sum of coeff * min_size over the rows
+  max_total  : T,    // sum of coeff * max_size over the bounded rows
+  unbounded  : bool, // some row has max_size UNBOUNDED
+  column_sum : T,
+}
+
+impl DiophantineSystemBuilder<u32> {
+  pub fn new() -> Self {
+    DiophantineSystemBuilder::default()
+  }
+}
+
+impl<T: DiophantineInt> Default for DiophantineSystemBuilder<T> {
+  fn default() -> Self {
+    DiophantineSystemBuilder {
+      system     : DiophantineSystem::with_capacity(0, 0),
+      error      : None,
+      min_total  : T::ZERO,
+      max_total  : T::ZERO,
+      unbounded  : false,
+      column_sum : T::ZERO,
+    }
+  }
+}
+
+impl<T: DiophantineInt> DiophantineSystemBuilder<T> {
+  /// Adds a row as if by `DiophantineSystem::insert_row`.
+  pub fn row(mut self, coeff: T, min_size: T, max_size: T) -> Self {
+    if self.error.is_none() {
+      match self.system.insert_row(coeff, min_size, max_size) {
+        Ok(()) => {
+          self.min_total = self.min_total.saturating_add(coeff * min_size);
+          if max_size == T::UNBOUNDED {
+            self.unbounded = true;
+          } else {
+            self.max_total = self.max_total.saturating_add(coeff * max_size);
+          }
+        }
+        Err(error) => self.error = Some(error),
+      }
+    }
+    self
+  }
+
+  /// Adds a column as if by `DiophantineSystem::insert_column`.
+  pub fn column(mut self, value: T) -> Self {
+    if self.error.is_none() {
+      match self.system.insert_column(value) {
+        Ok(())     => self.column_sum += value,
+        Err(error) => self.error = Some(error),
+      }
+    }
+    self
+  }
+
+  /// Returns the system, or the first error encountered while adding rows and columns. Fails with
+  /// `DiophantineError::Infeasible` if the column sum is less than the sum of the rows' `coeff * min_size` or greater
+  /// than the sum of their `coeff * max_size`, in which case the system has no solutions.
+  pub fn build(self) -> Result<DiophantineSystem<T>, DiophantineError> {
+    if let Some(error) = self.error {
+      return Err(error);
+    }
+    if self.min_total > self.column_sum || (!self.unbounded && self.max_total < self.column_sum) {
+      return Err(DiophantineError::Infeasible);
+    }
+    Ok(self.system)
+  }
+}
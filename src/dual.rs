@@ -0,0 +1,77 @@
+/*!
+
+Going the other way: recovering the coefficients $R$ from a matrix $M$ and the columns $C$.
+
+For given $M$ and $C$, the system $R \cdot M = C$ is a set of $m$ linear equations $\sum_i M_{i,j} R_i = C_j$ in the
+$n$ unknowns $R_i$, each with its own coefficients. Finding all positive integer solutions of such a system is a
+general integer programming problem rather than the AC matching problem this crate solves, and searching for $R$
+under constraints on $M$ is out of scope. What is cheap is the common case in which $M$ pins $R$ down: when the rows of
+$M$ are linearly independent, the equations have at most one solution, and `induced_coefficients` finds it by exact
+Gaussian elimination.
+
+*/
+
+use alloc::vec::Vec;
+
+/// The coefficients $R$ for which $R \cdot M = C$, where `matrix[i]` is row `i` of $M$ and `columns` is $C$, if $M$
+/// determines them. Returns `None` if the rows of `matrix` are linearly dependent, so that $R$ is not unique (or
+/// there are no rows), if no $R$ satisfies the equations, or if the unique solution is not a vector of positive
+/// integers, as the coefficients of a `DiophantineSystem` must be. Panics if the rows of `matrix` do not each have an
+/// entry for every column.
+pub fn induced_coefficients(matrix: &[Vec<u32>], columns: &[u32]) -> Option<Vec<u32>> {
+  let n = matrix.len();
+  assert!(matrix.iter().all(|row| row.len() == columns.len()), "matrix row length differs from column count");
+  if n == 0 {
+    return None;
+  }
+
+  // One equation per column: the coefficients of R_0..R_n, then the right-hand side.
+  let mut equations: Vec<Vec<i128>> = (0..columns.len())
+    .map(|j| matrix.iter().map(|row| row[j] as i128).chain(Some(columns[j] as i128)).collect())
+    .collect();
+
+  // Gauss-Jordan elimination, keeping the entries integral by cross-multiplying and dividing out common factors.
+  for i in 0..n {
+    let pivot = (i..equations.len()).find(|&k| equations[k][i] != 0)?;
+    equations.swap(i, pivot);
+    let pivot_row = equations[i].clone();
+    for (k, equation) in equations.iter_mut().enumerate() {
+      if k == i || equation[i] == 0 {
+        continue;
+      }
+      let factor = equation[i];
+      for (value, &p) in equation.iter_mut().zip(pivot_row.iter()) {
+        *value = value.checked_mul(pivot_row[i])?.checked_sub(p.checked_mul(factor)?)?;
+      }
+      let divisor = equation.iter().fold(0, |g, &value| gcd(g, value));
+      if divisor > 1 {
+        equation.iter_mut().for_each(|value| *value /= divisor);
+      }
+    }
+  }
+
+  // The equations beyond the first n are now 0 = rhs, which must hold.
+  if equations[n..].iter().any(|equation| equation[n] != 0) {
+    return None;
+  }
+  (0..n)
+    .map(|i| {
+      let (coeff, rhs) = (equations[i][i], equations[i][n]);
+      let value = rhs / coeff;
+      if value * coeff != rhs || value <= 0 || value > u32::MAX as i128 {
+        return None;
+      }
+      Some(value as u32)
+    })
+    .collect()
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+  let (mut a, mut b) = (a.abs(), b.abs());
+  while b != 0 {
+    let t = a % b;
+    a = b;
+    b = t;
+  }
+  a
+}
@@ -0,0 +1,78 @@
+/*!
+
+Errors reported for misuse of a `DiophantineSystem`.
+
+*/
+
+use core::fmt::Display;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum DiophantineError {
+  /// Rows and columns cannot be inserted after the first call to `solve()`.
+  SystemClosed,
+  /// Rows must have a positive coefficient and `min_size <= max_size`.
+  InvalidRow,
+  /// Column values must be nonnegative.
+  NegativeColumn,
+  /// `solve()` has not been called yet.
+  NoSolutionYet,
+  /// The last call to `solve()` returned false, so there is no current solution.
+  Exhausted,
+  /// The row or column index does not exist. `row_count` and `col_count` give the valid ranges `0..row_count` and
+  /// `0..col_count`.
+  IndexOutOfRange { row: usize, col: usize, row_count: usize, col_count: usize },
+  /// The system has no solutions, as found before the search: for instance, the row bounds cannot be met by the
+  /// column sum.
+  Infeasible,
+  /// `coeff * min_size` or `coeff * max_size` of a row, or the sum of the columns, does not fit in the integer type.
+  /// For a `SignedDiophantineSystem`, the sums of the rows' products or what is left of a column after them does not
+  /// fit.
+  Overflow,
+  /// The node budget ran out before the next solution was found.
+  Interrupted,
+  /// The solver caught itself in an inconsistent state, which is a bug in the solver. Only reported with the
+  /// `debug_checks` feature; otherwise the solver panics.
+  InternalInvariant,
+  /// The matrix passed to `from_matrix` has rows of the wrong length, a different number of rows than right-hand
+  /// sides, or rows with different coefficients.
+  InvalidMatrix,
+  /// The solubility vectors of a complex system would take more memory than allowed by
+  /// `set_max_solubility_memory()`.
+  TooLarge,
+  /// The solubility vectors of a complex system could not be allocated.
+  AllocationFailed,
+  /// The columns passed to `with_new_columns` are not as many as the system's own.
+  ColumnCountMismatch { expected: usize, found: usize },
+}
+
+impl Display for DiophantineError {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      DiophantineError::SystemClosed => write!(f, "rows and columns cannot be added once solving has started"),
+      DiophantineError::InvalidRow => write!(f, "row coefficient must be positive and min_size must not exceed max_size"),
+      DiophantineError::NegativeColumn => write!(f, "column value must be nonnegative"),
+      DiophantineError::NoSolutionYet => write!(f, "solve() not called"),
+      DiophantineError::Exhausted => write!(f, "non-existent solution"),
+      DiophantineError::IndexOutOfRange { row, col, row_count, col_count } => write!(
+        f,
+        "no entry at row {}, column {} of a system with {} rows and {} columns",
+        row, col, row_count, col_count
+      ),
+      DiophantineError::Infeasible => write!(f, "system has no solutions"),
+      DiophantineError::Overflow => write!(f, "row product or column sum overflows the integer type"),
+      DiophantineError::Interrupted => write!(f, "node budget exhausted before the next solution was found"),
+      DiophantineError::InternalInvariant => write!(f, "internal invariant of the solver violated"),
+      DiophantineError::InvalidMatrix => write!(f, "matrix does not describe a system R * M = C"),
+      DiophantineError::TooLarge => write!(f, "solubility vectors exceed the memory limit"),
+      DiophantineError::AllocationFailed => write!(f, "out of memory for the solubility vectors"),
+      DiophantineError::ColumnCountMismatch { expected, found } => write!(
+        f,
+        "expected {} columns but found {}",
+        expected, found
+      ),
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DiophantineError {}
@@ -0,0 +1,116 @@
+/*!
+
+Plain-text renderings of a system and of its current solution, for logging. Both are laid out as tables whose column
+widths adapt to the widest entry. For the system with rows `(3, 0, 4)`, `(2, 0, 4)` and `(1, 1, UNBOUNDED)` and
+columns `[6, 5, 4]`, `format_system()` gives
+
+```text
+coeff | min | max
+    3 |   0 |   4
+    2 |   0 |   4
+    1 |   1 | inf
+columns: 6 5 4
+```
+
+and `format_solution()` gives $M$ between the coefficients $R$ and the columns $C$, one row of $M$ per line:
+
+```text
+3 | 2 0 0 |
+2 | 0 1 0 |
+1 | 0 3 4 |
+  = 6 5 4
+```
+
+*/
+
+use alloc::{
+  string::{String, ToString},
+  vec::Vec,
+};
+
+use crate::{DiophantineInt, DiophantineSystem};
+
+impl<T: DiophantineInt> DiophantineSystem<T> {
+  /// The rows of the system in insertion order, one per line with the coefficient and the bounds on the row sum,
+  /// followed by the columns. An `UNBOUNDED` `max_size` is shown as `inf`.
+  pub fn format_system(&self) -> String {
+    let spec = self.spec();
+
+    let mut table = vec![vec!["coeff".to_string(), "min".to_string(), "max".to_string()]];
+    for row in spec.rows.iter() {
+      let max_size = if row.max_size == T::UNBOUNDED { "inf".to_string() } else { row.max_size.to_string() };
+      table.push(vec![row.coeff.to_string(), row.min_size.to_string(), max_size]);
+    }
+
+    let mut text = String::new();
+    for line in align(&table) {
+      text.push_str(&line.join(" | "));
+      text.push('\n');
+    }
+    text.push_str("columns:");
+    for column in spec.columns.iter() {
+      text.push(' ');
+      text.push_str(&column.to_string());
+    }
+    text.push('\n');
+    text
+  }
+
+  /// The current solution $M$ with rows in insertion order, each preceded by its coefficient, and the columns
+  /// underneath. Panics if there is no current solution.
+  pub fn format_solution(&self) -> String {
+    let spec   = self.spec();
+    let matrix = self.solution_matrix();
+
+    let mut table: Vec<Vec<String>> = matrix
+      .iter()
+      .zip(spec.rows.iter())
+      .map(|(entries, row)| {
+        let mut line = vec![row.coeff.to_string()];
+        line.extend(entries.iter().map(|entry| entry.to_string()));
+        line
+      })
+      .collect();
+    let mut last = vec![String::new()];
+    last.extend(spec.columns.iter().map(|column| column.to_string()));
+    table.push(last);
+
+    let mut text  = String::new();
+    let     lines = align(&table);
+    for (i, line) in lines.iter().enumerate() {
+      let is_last = i + 1 == lines.len();
+      text.push_str(&line[0]);
+      text.push_str(if is_last { " = " } else { " | " });
+      text.push_str(&line[1..].join(" "));
+      if !is_last {
+        text.push_str(" |");
+      }
+      text.push('\n');
+    }
+    text
+  }
+}
+
+/// Right-aligns the entries of each column of `table` to the width of the column's widest entry.
+fn align(table: &[Vec<String>]) -> Vec<Vec<String>> {
+  let mut widths: Vec<usize> = Vec::new();
+  for line in table.iter() {
+    for (i, entry) in line.iter().enumerate() {
+      if i == widths.len() {
+        widths.push(0);
+      }
+      widths[i] = widths[i].max(entry.chars().count());
+    }
+  }
+
+  table
+    .iter()
+    .map(|line| {
+      line
+        .iter()
+        .zip(widths.iter())
+        .map(|(entry, &width)| format!("{:>width$}", entry, width = width))
+        .collect()
+    })
+    .collect()
+}
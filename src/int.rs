@@ -0,0 +1,132 @@
+/*!
+
+The integer types over which a `DiophantineSystem` can be instantiated.
+
+The solver only ever stores nonnegative values: coefficients, row sizes, column values, and the entries of the
+solution matrix. Any primitive integer type wide enough for the column sum of the system can be used. Signed types are
+supported for convenience but gain nothing over their unsigned counterparts.
+
+Wide types such as `u128` make large column sums representable, but they do not make large column values cheap. A
+complex system allocates a solubility vector for every row, indexed by column value, so its memory use is
+proportional to the number of rows times the largest column value. Wide types are practical for simple systems, or for
+complex systems whose largest column value is moderate.
+
+Arbitrary precision integers are not supported: the solver copies values freely, so the trait requires `Copy`.
+
+*/
+
+use core::{
+  convert::TryFrom,
+  fmt::{Debug, Display},
+  hash::Hash,
+  ops::{Add, AddAssign, Div, Mul, Sub, SubAssign},
+};
+
+/// Integer types usable as the entries of a `DiophantineSystem`.
+pub trait DiophantineInt:
+  Copy
+  + Ord
+  + Hash
+  + Default
+  + Debug
+  + Display
+  + Add<Output = Self>
+  + Sub<Output = Self>
+  + Mul<Output = Self>
+  + Div<Output = Self>
+  + AddAssign
+  + SubAssign
+{
+  const ZERO: Self;
+  const ONE : Self;
+  /// The largest representable value.
+  const MAX : Self;
+
+  /// A `max_size` of `UNBOUNDED` means the row sum is bounded only by the column total.
+  const UNBOUNDED: Self = Self::MAX;
+
+  /// Converts a nonnegative value to an index. Only used for values bounded by the length of an existing vector.
+  fn to_usize(self) -> usize;
+
+  /// Converts an index or count to `Self`.
+  fn from_usize(value: usize) -> Self;
+
+  /// Converts to an `i64`, or `None` if the value does not fit.
+  fn to_i64(self) -> Option<i64>;
+
+  /// Converts a nonnegative value to a `u128`, which holds every such value of every implementing type.
+  fn to_u128(self) -> u128;
+
+  /// `self + rhs`, or `None` on overflow.
+  fn checked_add(self, rhs: Self) -> Option<Self>;
+
+  /// `self * rhs`, or `None` on overflow.
+  fn checked_mul(self, rhs: Self) -> Option<Self>;
+
+  /// `self + rhs`, or `MAX` on overflow. Only used for nonnegative values.
+  #[inline(always)]
+  fn saturating_add(self, rhs: Self) -> Self {
+    self.checked_add(rhs).unwrap_or(Self::MAX)
+  }
+
+  /// `self * rhs`, or `MAX` on overflow. Only used for nonnegative values.
+  #[inline(always)]
+  fn saturating_mul(self, rhs: Self) -> Self {
+    self.checked_mul(rhs).unwrap_or(Self::MAX)
+  }
+}
+
+macro_rules! impl_diophantine_int {
+  ($($t:ty),*) => {
+    $(
+      impl DiophantineInt for $t {
+        const ZERO: Self = 0;
+        const ONE : Self = 1;
+        const MAX : Self = <$t>::MAX;
+
+        #[inline(always)]
+        #[allow(unused_comparisons)]
+        fn to_usize(self) -> usize {
+          debug_assert!(self >= 0);
+          self as usize
+        }
+
+        #[inline(always)]
+        fn from_usize(value: usize) -> Self {
+          value as $t
+        }
+
+        #[inline(always)]
+        #[allow(unused_comparisons)]
+        fn to_u128(self) -> u128 {
+          debug_assert!(self >= 0);
+          self as u128
+        }
+
+        #[inline(always)]
+        fn to_i64(self) -> Option<i64> {
+          i64::try_from(self).ok()
+        }
+
+        #[inline(always)]
+        fn checked_add(self, rhs: Self) -> Option<Self> {
+          <$t>::checked_add(self, rhs)
+        }
+
+        #[inline(always)]
+        fn checked_mul(self, rhs: Self) -> Option<Self> {
+          <$t>::checked_mul(self, rhs)
+        }
+      }
+    )*
+  };
+}
+
+impl_diophantine_int!(u32, u64, u128, usize, i32, i64);
+
+/// Signed integer types, usable as the entries of a `SignedDiophantineSystem`, whose coefficients and columns may be
+/// negative.
+pub trait SignedDiophantineInt: DiophantineInt {}
+
+impl SignedDiophantineInt for i32 {}
+impl SignedDiophantineInt for i64 {}
@@ -0,0 +1,83 @@
+/*!
+
+LaTeX renderings of a system and of its current solution, for typesetting results. Both use the `pmatrix` environment
+of `amsmath`, with rows in insertion order. For the system with rows `(3, 0, 4)`, `(2, 0, 4)` and `(1, 1, UNBOUNDED)`
+and columns `[6, 5, 4]`, `system_latex()` gives the equation $R \cdot M = C$ with the unknown entries $M_{i,j}$,
+indexed from 0 as by `solution(i, j)`,
+
+```text
+\begin{pmatrix}
+3 & 2 & 1
+\end{pmatrix}
+\begin{pmatrix}
+M_{0,0} & M_{0,1} & M_{0,2} \\
+M_{1,0} & M_{1,1} & M_{1,2} \\
+M_{2,0} & M_{2,1} & M_{2,2}
+\end{pmatrix}
+=
+\begin{pmatrix}
+6 & 5 & 4
+\end{pmatrix}
+```
+
+and `solution_latex()`, at one of its solutions, gives $M$:
+
+```text
+\begin{pmatrix}
+2 & 0 & 0 \\
+0 & 1 & 0 \\
+0 & 3 & 4
+\end{pmatrix}
+```
+
+The row bounds are not shown.
+
+*/
+
+use alloc::{
+  string::{String, ToString},
+  vec::Vec,
+};
+
+use crate::{DiophantineInt, DiophantineSystem};
+
+impl<T: DiophantineInt> DiophantineSystem<T> {
+  /// The current solution $M$ as a LaTeX `pmatrix`, with rows in insertion order. Panics if there is no current
+  /// solution.
+  pub fn solution_latex(&self) -> String {
+    let matrix: Vec<Vec<String>> = self
+      .solution_matrix()
+      .iter()
+      .map(|row| row.iter().map(|entry| entry.to_string()).collect())
+      .collect();
+    pmatrix(&matrix)
+  }
+
+  /// The system as the LaTeX equation $R \cdot M = C$, with $R$ and $C$ as row vectors and the entries of $M$ as the
+  /// unknowns $M_{i,j}$, indexed from 0 in insertion order. The row bounds are not shown.
+  pub fn system_latex(&self) -> String {
+    let spec = self.spec();
+
+    let coeffs  = vec![spec.rows.iter().map(|row| row.coeff.to_string()).collect()];
+    let columns = vec![spec.columns.iter().map(|column| column.to_string()).collect()];
+    let unknowns: Vec<Vec<String>> = (0..spec.rows.len())
+      .map(|i| (0..spec.columns.len()).map(|j| format!("M_{{{},{}}}", i, j)).collect())
+      .collect();
+
+    let mut text = pmatrix(&coeffs);
+    text.push_str(&pmatrix(&unknowns));
+    text.push_str("=\n");
+    text.push_str(&pmatrix(&columns));
+    text
+  }
+}
+
+/// A `pmatrix` environment with the given entries, one line per row.
+fn pmatrix(matrix: &[Vec<String>]) -> String {
+  let rows: Vec<String> = matrix.iter().map(|row| row.join(" & ")).collect();
+
+  let mut text = String::from("\\begin{pmatrix}\n");
+  text.push_str(&rows.join(" \\\\\n"));
+  text.push_str("\n\\end{pmatrix}\n");
+  text
+}
@@ -6,7 +6,7 @@ A solver for linear Diophantine systems associated with AC and ACU matching. The
 
 The system of linear (algebraic) equations
 
-```
+```text
 2x + 3y +  z + 5w + 6u + 0v == 26,
  x + 2y + 3z + 4w + 5u + 2v == 28,
 3x + 2y + 5z +  w + 7u + 3v == 32,
@@ -16,7 +16,7 @@ The system of linear (algebraic) equations
 ```
 has solution x = 1, y = 2, z = 2, w = 2, u = 1, v = 2. So we have
 
-```
+```text
  ┌─ ─┐T ┌─           ─┐   ┌────┐T
  │ 1 │  │ 2 1 3 2 5 1 │   │ 26 │
  │ 2 │  │ 3 2 2 1 3 4 │   │ 28 │
@@ -29,37 +29,40 @@ has solution x = 1, y = 2, z = 2, w = 2, u = 1, v = 2. So we have
    R   *       M        =   C
 ```
 
-We solve an alternative problem in which R and C are given and M is solved for. We constrain the matrix M by giving values `min_j` and `max_j` such that the sum of values in row `j` has minimum value `min_j` and maximum value `max_j`.
+We solve an alternative problem in which R and C are given and M is solved for. We constrain the matrix M by giving values `min_j` and `max_j` such that the sum of values in row `j` has minimum value `min_j` and maximum value `max_j`. Going the other way, `induced_coefficients` recovers R from M and C when M determines it.
 
-In general, there may be multiple solutions. To generate solutions, call `System.solve()` until it returns false. When it returns true, the solution is extracted with `System.solution(row, column)`.
+In general, there may be multiple solutions. To generate solutions, call `System.solve()` until it returns false. When it returns true, the solution is extracted with `System.solution(row, column)`, or with `System.solution_unchecked(row, column)`, which panics instead of returning an error.
+Alternatively, `System.solutions()` returns an iterator that drives `solve()` and yields an owned `Solution` snapshot of the matrix for each solution found.
 
 
-```rust
-
+```rust,no_run
+# use diophantine::{DiophantineError, DiophantineSystem};
+# fn main() -> Result<(), DiophantineError> {
 let mut system = DiophantineSystem::new(6, 6);
-system.insert_row(1, 10, 20);
-system.insert_row(2, 11, 19);
-system.insert_row(2, 15, 20);
-system.insert_row(2, 15, 20);
-system.insert_row(1, 30, 38);
-system.insert_row(2, 12, 16);
-system.insert_column(26);
-system.insert_column(28);
-system.insert_column(32);
-system.insert_column(25);
-system.insert_column(41);
-system.insert_column(26);
+system.insert_row(1, 10, 20)?;
+system.insert_row(2, 11, 19)?;
+system.insert_row(2, 15, 20)?;
+system.insert_row(2, 15, 20)?;
+system.insert_row(1, 30, 38)?;
+system.insert_row(2, 12, 16)?;
+system.insert_column(26)?;
+system.insert_column(28)?;
+system.insert_column(32)?;
+system.insert_column(25)?;
+system.insert_column(41)?;
+system.insert_column(26)?;
 
 while system.solve() {
   println!("\nSolution:");
   for row in 0..6 {
     for col in 0..6 {
-      print!("{}  ", system.solution(row, col));
+      print!("{}  ", system.solution(row, col)?);
     }
     println!();
   }
 }
-
+# Ok(())
+# }
 ```
 
 # Description from Maude
@@ -71,7 +74,7 @@ Based on:
 > _Journal of Automated Reasoning_, pp35-51, 28(1), 2002.
 
 Given an $n$-component vector of positive integers $R$ and an $m$-component vector
-of positive integers $C$ a solution is an $n\times m$ matrix $M$ of natural numbers such
+of nonnegative integers $C$ a solution is an $n\times m$ matrix $M$ of natural numbers such
 that
 $R \cdot M = C$.
 The intuition is that $M_{i,j}$ is the multiplicity of the $j$th constant assigned
@@ -118,84 +121,123 @@ linear combination of any final segment of the sorted $R$. Thus we can rule
 out one cause of failure for partial solutions. For complex systems we keep
 a "solubility vector" which allows us to detect this kind of failure early
 and prune the useless branches from the search.
+
+# `no_std`
+
+The crate only needs `alloc`. Building with `--no-default-features` drops the `std` feature and with it the
+`std::error::Error` implementation, `DiophantineSystem::dump_info`, the threaded `par_count_solutions` and
+`solve_into_channel`, and the `TRACE_CALLS` and `dio_stats` features, which print.
 */
 
+#![cfg_attr(all(not(feature = "std"), not(test)), no_std)]
+
+#[cfg(any(feature = "std", test))]
+extern crate core;
+#[cfg(all(not(feature = "std"), not(test)))]
+#[macro_use]
+extern crate alloc;
+#[cfg(any(feature = "std", test))]
+extern crate alloc;
+
+#[cfg(any(feature = "testing", test))]
+mod brute;
+mod builder;
+mod dual;
+mod error;
+mod format;
+mod int;
+#[cfg(feature = "latex")]
+mod latex;
+mod merge;
+#[cfg(feature = "std")]
+mod parallel;
+mod sample;
+mod signed;
+mod solubility;
 mod system;
+mod solution;
+mod spec;
+mod stats;
 pub(crate) mod row;
 
-pub use system::DiophantineSystem;
-
-// TODO: Templatize integer types.
+pub use builder::DiophantineSystemBuilder;
+pub use dual::induced_coefficients;
+pub use error::DiophantineError;
+pub use int::{DiophantineInt, SignedDiophantineInt};
+pub use signed::SignedDiophantineSystem;
+pub use solubility::SolubilityTables;
+pub use system::{DiophantineSystem, Infeasibility, RowOrder, SolveOutcome, SystemClass, UNBOUNDED};
+pub use solution::{Solution, Solutions};
+pub use spec::{RowSpec, SystemSpec};
+pub use stats::SolveStats;
 
 
 #[derive(Copy, Clone, Default, Debug)]
-pub(crate) struct Select {
-  pub(crate) base      : u32,	// base value for element of $M$ (0 for simple systems)
-  pub(crate) extra     : u32,	// extra value representing current state of solution
-  pub(crate) max_extra : u32,	// maximum for extra
+pub(crate) struct Select<T: DiophantineInt> {
+  pub(crate) base      : T,	// base value for element of $M$ (0 for simple systems)
+  pub(crate) extra     : T,	// extra value representing current state of solution
+  pub(crate) max_extra : T,	// maximum for extra
 }
 
 
-///	In a complex system, for each row with coefficient $R_i$ and for each possible
-///	column value $V$ we compute and store the minimum and maximum $K$ such that
+/// In a complex system, for each row with coefficient $R_i$ and for each possible
+/// column value $V$ we compute and store the minimum and maximum $K$ such that
 /// $V - K*R_i$ can be expressed as a natural number linear combination over
-///	$R_j$ for $j > i$, respecting the maximum allowable sums but not the minimum
-///	allowable sums (since some other column may make up the minimum).
-///	If no such (natural number) $K$ exists we store `min = max = INSOLUBLE`.
-#[derive(Copy, Clone, Default, Debug)]
-pub(crate) struct Soluble {
-  pub(crate) min: i32,	// minimum assignment to row for given column value
-  pub(crate) max: i32,	// maximum assignment to row for given column value
+/// $R_j$ for $j > i$, respecting the maximum allowable sums but not the minimum
+/// allowable sums (since some other column may make up the minimum).
+/// If no such (natural number) $K$ exists we store `min = max = INSOLUBLE`.
+/// See `DiophantineSystem::solubility`.
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub struct Soluble<T: DiophantineInt> {
+  pub min: T,	// minimum assignment to row for given column value
+  pub max: T,	// maximum assignment to row for given column value
 }
 
-impl Soluble {
-  /// A special value used as a marker in the `Soluble` struct.
-  pub(crate) const INSOLUBLE: i32 = -1;
+impl<T: DiophantineInt> Soluble<T> {
+  /// A special value used as a marker in the `Soluble` struct. No assignment can reach it, since assignments are
+  /// bounded by the largest column value.
+  pub const INSOLUBLE: T = T::MAX;
   /// A special instance
-  pub(crate) const INSOLUBLE_STRUCT: Soluble = Soluble{
-    min: Soluble::INSOLUBLE,
-    max: Soluble::INSOLUBLE,
+  #[cfg(not(feature = "compact_solubility"))]
+  pub(crate) const INSOLUBLE_STRUCT: Soluble<T> = Soluble{
+    min: Self::INSOLUBLE,
+    max: Self::INSOLUBLE,
   };
+
+  /// Whether the column value is soluble at all, that is, whether `min` and `max` are not `INSOLUBLE`.
+  pub fn is_soluble(&self) -> bool {
+    self.min != Self::INSOLUBLE
+  }
 }
 
 // Miscellaneous utility functions
 
+/// $\lceil dividend / divisor \rceil$, for any signs of the operands. Never overflows, except for `T::MIN / -1` of a
+/// signed type, which panics as it does for `/`. Panics if `divisor` is zero.
+#[must_use]
 #[inline(always)]
-pub(crate) fn ceiling_division(dividend: i32, divisor: i32) -> i32 {
-    if divisor > 0 {
-      if dividend >= 0 {
-          (dividend + divisor - 1) / divisor
-      } else {
-          -((-dividend)  / divisor)
-      }
-    }
-    else {
-      assert!(divisor < 0);
-      if dividend >= 0 {
-        -(dividend / (-divisor))
-      } else {
-        ((-dividend) + (-divisor) - 1) / (-divisor)
-      }
-    }
+pub fn ceiling_division<T: DiophantineInt>(dividend: T, divisor: T) -> T {
+  let quotient = dividend / divisor; // rounded toward zero
+  if quotient * divisor != dividend && (dividend > T::ZERO) == (divisor > T::ZERO) {
+    // A positive quotient was rounded down.
+    quotient + T::ONE
+  } else {
+    quotient
+  }
 }
 
+/// $\lfloor dividend / divisor \rfloor$, for any signs of the operands. Never overflows, except for `T::MIN / -1` of a
+/// signed type, which panics as it does for `/`. Panics if `divisor` is zero.
+#[must_use]
 #[inline(always)]
-pub(crate) fn floor_division(dividend: i32, divisor: i32) -> i32 {
-    if divisor > 0 {
-      if dividend >= 0 {
-          dividend / divisor
-      } else {
-          -((divisor - dividend - 1) / divisor)
-      }
-    }
-    else {
-      assert!(divisor < 0);
-      if dividend >= 0 {
-        -((dividend - divisor - 1) / (-divisor))
-      } else {
-        (-dividend) / (-divisor)
-      }
-    }
+pub fn floor_division<T: DiophantineInt>(dividend: T, divisor: T) -> T {
+  let quotient = dividend / divisor; // rounded toward zero
+  if quotient * divisor != dividend && (dividend > T::ZERO) != (divisor > T::ZERO) {
+    // A negative quotient was rounded up.
+    quotient - T::ONE
+  } else {
+    quotient
+  }
 }
 
 
@@ -208,18 +250,18 @@ mod tests {
     fn system_solver_test() {
 
       let mut system = DiophantineSystem::new(6, 6);
-      system.insert_row(1, 14, 14);
-      system.insert_row(2, 15, 15);
-      system.insert_row(2, 17, 17);
-      system.insert_row(2, 18, 18);
-      system.insert_row(1, 34, 34);
-      system.insert_row(2, 15, 15);
-      system.insert_column(26);
-      system.insert_column(28);
-      system.insert_column(32);
-      system.insert_column(25);
-      system.insert_column(41);
-      system.insert_column(26);
+      system.insert_row(1, 14, 14).unwrap();
+      system.insert_row(2, 15, 15).unwrap();
+      system.insert_row(2, 17, 17).unwrap();
+      system.insert_row(2, 18, 18).unwrap();
+      system.insert_row(1, 34, 34).unwrap();
+      system.insert_row(2, 15, 15).unwrap();
+      system.insert_column(26).unwrap();
+      system.insert_column(28).unwrap();
+      system.insert_column(32).unwrap();
+      system.insert_column(25).unwrap();
+      system.insert_column(41).unwrap();
+      system.insert_column(26).unwrap();
 
       // println!("Solve: {}", system.solve());
       let solution_count = 8;
@@ -230,7 +272,7 @@ mod tests {
         println!("\nSolution:");
         for row in 0..6 {
           for col in 0..6 {
-            print!("{}  ", system.solution(row, col));
+            print!("{}  ", system.solution_unchecked(row, col));
           }
           println!();
         }
@@ -247,4 +289,2579 @@ mod tests {
       println!("Done!")
 
     }
+
+    #[test]
+    fn solutions_iterator_test() {
+      let mut system = DiophantineSystem::new(2, 2);
+      system.insert_row(2, 1, 3).unwrap();
+      system.insert_row(1, 1, 5).unwrap();
+      system.insert_column(3).unwrap();
+      system.insert_column(4).unwrap();
+
+      let solutions: Vec<Solution> = system.solutions().collect();
+      assert_eq!(solutions.len(), 5);
+      for solution in solutions.iter() {
+        for c in 0..2 {
+          let value = 2 * solution.get(0, c) + solution.get(1, c);
+          assert_eq!(value, [3, 4][c]);
+        }
+      }
+      // The snapshots are distinct, and the iterator stays exhausted.
+      assert!(solutions.windows(2).all(|pair| pair[0] != pair[1]));
+      assert!(system.solutions().next().is_none());
+
+      let mut system = DiophantineSystem::new(2, 2);
+      system.insert_row(3, 1, 2).unwrap();
+      system.insert_row(2, 1, 3).unwrap();
+      system.insert_column(5).unwrap();
+      system.insert_column(7).unwrap();
+
+      let solutions: Vec<Solution> = system.solutions().take(10).collect();
+      assert_eq!(solutions.len(), 1);
+      assert_eq!(solutions[0].row(0), &[1, 1]);
+      assert_eq!(solutions[0].row(1), &[1, 2]);
+    }
+
+    #[test]
+    fn solution_matrix_test() {
+      // Rows are inserted in ascending coefficient order, so they are permuted by the solver.
+      let mut system = DiophantineSystem::new(3, 3);
+      system.insert_row(1, 1, 3).unwrap();
+      system.insert_row(2, 0, 4).unwrap();
+      system.insert_row(3, 0, 4).unwrap();
+      system.insert_column(6).unwrap();
+      system.insert_column(5).unwrap();
+      system.insert_column(4).unwrap();
+
+      let mut count = 0;
+      while system.solve() {
+        let matrix = system.solution_matrix();
+        let flat   = system.solution_flat();
+        assert_eq!(matrix.len(), 3);
+        for r in 0..3 {
+          assert_eq!(matrix[r].len(), 3);
+          for c in 0..3 {
+            assert_eq!(matrix[r][c], system.solution_unchecked(r, c));
+            assert_eq!(flat[r * 3 + c], system.solution_unchecked(r, c));
+          }
+        }
+        for c in 0..3 {
+          assert_eq!(matrix[0][c] + 2 * matrix[1][c] + 3 * matrix[2][c], [6, 5, 4][c]);
+        }
+        count += 1;
+      }
+      assert_eq!(count, 17);
+    }
+
+    #[test]
+    fn generic_integer_test() {
+      // The column sum exceeds `u32::MAX`.
+      let mut system: DiophantineSystem<u64> = DiophantineSystem::with_capacity(2, 2);
+      system.insert_row(1, 0, 0).unwrap();
+      system.insert_row(1, 0, u64::UNBOUNDED).unwrap();
+      system.insert_column(5_000_000_000).unwrap();
+      system.insert_column(1_000_000_000).unwrap();
+
+      assert!(system.solve());
+      assert_eq!(system.solution_matrix(), vec![vec![0, 0], vec![5_000_000_000, 1_000_000_000]]);
+      assert!(!system.solve());
+
+      let mut system: DiophantineSystem<i64> = DiophantineSystem::with_capacity(2, 2);
+      system.insert_row(3, 1, 2).unwrap();
+      system.insert_row(2, 1, 3).unwrap();
+      system.insert_column(5).unwrap();
+      system.insert_column(7).unwrap();
+      assert_eq!(system.solutions().count(), 1);
+    }
+
+    #[test]
+    fn misuse_errors_test() {
+      let mut system = DiophantineSystem::new(2, 2);
+      assert_eq!(system.insert_row(0, 1, 3), Err(DiophantineError::InvalidRow));
+      assert_eq!(system.insert_row(2, 4, 3), Err(DiophantineError::InvalidRow));
+      system.insert_row(2, 1, 3).unwrap();
+      system.insert_row(1, 1, 5).unwrap();
+      system.insert_column(3).unwrap();
+      system.insert_column(4).unwrap();
+      assert_eq!(system.solution(0, 0), Err(DiophantineError::NoSolutionYet));
+
+      assert!(system.solve());
+      assert!(system.solution(1, 1).is_ok());
+      assert_eq!(
+        system.solution(2, 0),
+        Err(DiophantineError::IndexOutOfRange { row: 2, col: 0, row_count: 2, col_count: 2 })
+      );
+      assert_eq!(
+        system.solution(0, 2),
+        Err(DiophantineError::IndexOutOfRange { row: 0, col: 2, row_count: 2, col_count: 2 })
+      );
+      assert_eq!(
+        system.solution(0, 2).unwrap_err().to_string(),
+        "no entry at row 0, column 2 of a system with 2 rows and 2 columns"
+      );
+      let mut signed = SignedDiophantineSystem::new(1, 1);
+      signed.insert_row(1, 0, 4).unwrap();
+      signed.insert_column(2).unwrap();
+      assert!(signed.solve());
+      assert_eq!(
+        signed.solution(1, 0),
+        Err(DiophantineError::IndexOutOfRange { row: 1, col: 0, row_count: 1, col_count: 1 })
+      );
+      assert_eq!(system.insert_row(1, 1, 1), Err(DiophantineError::SystemClosed));
+      assert_eq!(system.insert_column(1), Err(DiophantineError::SystemClosed));
+
+      while system.solve() {}
+      assert_eq!(system.solution(0, 0), Err(DiophantineError::Exhausted));
+    }
+
+    #[test]
+    fn count_solutions_test() {
+      let mut system = DiophantineSystem::new(3, 3);
+      system.insert_row(3, 0, 4).unwrap();
+      system.insert_row(2, 0, 4).unwrap();
+      system.insert_row(1, 1, 3).unwrap();
+      system.insert_column(6).unwrap();
+      system.insert_column(5).unwrap();
+      system.insert_column(4).unwrap();
+      assert_eq!(system.count_solutions(), 17);
+      assert_eq!(system.solution(0, 0), Err(DiophantineError::Exhausted));
+      assert_eq!(system.count_solutions(), 0);
+
+      // Solutions already consumed are not counted.
+      let mut system = DiophantineSystem::new(2, 2);
+      system.insert_row(2, 1, 3).unwrap();
+      system.insert_row(1, 1, 5).unwrap();
+      system.insert_column(3).unwrap();
+      system.insert_column(4).unwrap();
+      assert!(system.solve());
+      assert_eq!(system.count_solutions(), 4);
+    }
+
+    #[test]
+    fn reset_test() {
+      // A complex system, which mutates the columns in place.
+      let mut system = DiophantineSystem::new(3, 3);
+      system.insert_row(3, 0, 4).unwrap();
+      system.insert_row(2, 0, 4).unwrap();
+      system.insert_row(1, 1, 3).unwrap();
+      system.insert_column(6).unwrap();
+      system.insert_column(5).unwrap();
+      system.insert_column(4).unwrap();
+      let first: Vec<Solution> = system.solutions().collect();
+      assert_eq!(first.len(), 17);
+
+      system.reset();
+      let second: Vec<Solution> = system.solutions().collect();
+      assert_eq!(first, second);
+
+      // Reset in the middle of the enumeration.
+      system.reset();
+      for _ in 0..5 {
+        assert!(system.solve());
+      }
+      system.reset();
+      let third: Vec<Solution> = system.solutions().collect();
+      assert_eq!(first, third);
+
+      // A simple system with an unbounded row.
+      let mut system = DiophantineSystem::new(2, 2);
+      system.insert_row(1, 1, 3).unwrap();
+      system.insert_row(1, 0, u32::UNBOUNDED).unwrap();
+      system.insert_column(3).unwrap();
+      system.insert_column(4).unwrap();
+      let first: Vec<Solution> = system.solutions().collect();
+      system.reset();
+      let second: Vec<Solution> = system.solutions().collect();
+      assert!(!first.is_empty());
+      assert_eq!(first, second);
+    }
+
+    #[test]
+    fn clone_test() {
+      let mut system = DiophantineSystem::new(3, 3);
+      system.insert_row(3, 0, 4).unwrap();
+      system.insert_row(2, 0, 4).unwrap();
+      system.insert_row(1, 1, 3).unwrap();
+      system.insert_column(6).unwrap();
+      system.insert_column(5).unwrap();
+      system.insert_column(4).unwrap();
+
+      let mut unsolved = system.clone();
+      for _ in 0..7 {
+        assert!(system.solve());
+      }
+      let mut fork = system.clone();
+      assert_eq!(fork.solution_matrix(), system.solution_matrix());
+
+      let rest: Vec<Solution>        = system.solutions().collect();
+      let forked_rest: Vec<Solution> = fork.solutions().collect();
+      assert_eq!(rest.len(), 10);
+      assert_eq!(rest, forked_rest);
+
+      // A clone of the unsolved system is unaffected by solving the original.
+      assert_eq!(unsolved.count_solutions(), 17);
+    }
+
+    #[test]
+    fn spec_round_trip_test() {
+      let mut system = DiophantineSystem::new(3, 3);
+      system.insert_row(1, 1, 3).unwrap();
+      system.insert_row(3, 0, 4).unwrap();
+      system.insert_row(2, 0, u32::UNBOUNDED).unwrap();
+      system.insert_column(6).unwrap();
+      system.insert_column(5).unwrap();
+      system.insert_column(4).unwrap();
+
+      let spec = system.spec();
+      assert_eq!(spec.rows[2], RowSpec { coeff: 2, min_size: 0, max_size: u32::UNBOUNDED });
+      assert_eq!(spec.columns, vec![6, 5, 4]);
+
+      // The description is unchanged by solving, and rebuilds an equivalent open system.
+      let expected: Vec<Solution> = system.solutions().collect();
+      assert_eq!(system.spec(), spec);
+      let mut rebuilt = spec.to_system().unwrap();
+      assert_eq!(rebuilt.solutions().collect::<Vec<Solution>>(), expected);
+    }
+
+    #[test]
+    fn row_and_column_getters_test() {
+      let rows    = [(1, 1, 3), (3, 0, 4), (2, 0, u32::UNBOUNDED)];
+      let columns = [6, 5, 4];
+      let mut system = DiophantineSystem::new(3, 3);
+      for &(coeff, min_size, max_size) in rows.iter() {
+        system.insert_row(coeff, min_size, max_size).unwrap();
+      }
+      for &value in columns.iter() {
+        system.insert_column(value).unwrap();
+      }
+
+      // Both before solving and mid-search, when rows are permuted and columns are mutated.
+      for &solve in [false, true].iter() {
+        if solve {
+          assert!(system.solve());
+          assert!(system.solve());
+        }
+        for (i, &(coeff, min_size, max_size)) in rows.iter().enumerate() {
+          assert_eq!(system.row_coeff(i), coeff);
+          assert_eq!(system.row_min_size(i), min_size);
+          assert_eq!(system.row_max_size(i), max_size);
+        }
+        for (j, &value) in columns.iter().enumerate() {
+          assert_eq!(system.column_value(j), value);
+        }
+      }
+
+      // Trivially infeasible, so `precompute()` fails before sorting the rows.
+      let mut system = DiophantineSystem::new(2, 1);
+      system.insert_row(1, 0, 1).unwrap();
+      system.insert_row(3, 0, 1).unwrap();
+      system.insert_column(9).unwrap();
+      assert!(!system.solve());
+      assert_eq!(system.row_coeff(1), 3);
+    }
+
+    #[test]
+    fn unbounded_row_test() {
+      let build = |max_size| {
+        let mut system = DiophantineSystem::new(3, 2);
+        system.insert_row(2, 0, 2).unwrap();
+        system.insert_row(1, 1, max_size).unwrap();
+        system.insert_row(3, 1, 1).unwrap();
+        system.insert_column(7).unwrap();
+        system.insert_column(5).unwrap();
+        system
+      };
+
+      let unbounded: Vec<Solution> = build(UNBOUNDED).solutions().collect();
+      let bounded: Vec<Solution>   = build(12).solutions().collect();
+      assert!(!unbounded.is_empty());
+      assert_eq!(unbounded, bounded);
+    }
+
+    #[test]
+    fn for_each_solution_test() {
+      let mut system = DiophantineSystem::new(2, 2);
+      system.insert_row(2, 1, 3).unwrap();
+      system.insert_row(1, 1, 5).unwrap();
+      system.insert_column(3).unwrap();
+      system.insert_column(4).unwrap();
+
+      let mut expected = system.clone();
+      let mut seen = Vec::new();
+      system.for_each_solution(|system| {
+        seen.push(system.solution_matrix());
+        seen.len() < 3
+      });
+      assert_eq!(seen.len(), 3);
+      // Stopping early leaves the third solution current.
+      assert_eq!(system.solution_matrix(), seen[2]);
+
+      let expected: Vec<Vec<Vec<u32>>> = expected
+        .solutions()
+        .take(3)
+        .map(|s| (0..2).map(|r| s.row(r).to_vec()).collect())
+        .collect();
+      assert_eq!(seen, expected);
+    }
+
+    #[test]
+    fn verify_current_solution_test() {
+      let mut system = DiophantineSystem::new(3, 3);
+      system.insert_row(3, 0, 4).unwrap();
+      system.insert_row(2, 0, 4).unwrap();
+      system.insert_row(1, 1, 3).unwrap();
+      system.insert_column(6).unwrap();
+      system.insert_column(5).unwrap();
+      system.insert_column(4).unwrap();
+      assert!(!system.verify_current_solution());
+
+      let mut count = 0;
+      system.for_each_solution(|system| {
+        assert!(system.verify_current_solution());
+        count += 1;
+        true
+      });
+      assert_eq!(count, 17);
+      assert!(!system.verify_current_solution());
+    }
+
+    #[test]
+    fn solution_nonzero_test() {
+      let mut system = DiophantineSystem::new(3, 3);
+      system.insert_row(1, 1, 3).unwrap();
+      system.insert_row(2, 0, 4).unwrap();
+      system.insert_row(3, 0, 4).unwrap();
+      system.insert_column(6).unwrap();
+      system.insert_column(5).unwrap();
+      system.insert_column(4).unwrap();
+
+      system.for_each_solution(|system| {
+        let matrix  = system.solution_matrix();
+        let entries = system.solution_nonzero();
+        let mut expected = Vec::new();
+        for (r, row) in matrix.iter().enumerate() {
+          for (c, &value) in row.iter().enumerate() {
+            if value > 0 {
+              expected.push((r, c, value));
+            }
+          }
+        }
+        assert_eq!(entries, expected);
+        true
+      });
+    }
+
+    #[test]
+    fn debug_test() {
+      let mut system = DiophantineSystem::new(2, 2);
+      system.insert_row(2, 1, 3).unwrap();
+      system.insert_row(1, 1, 5).unwrap();
+      system.insert_column(3).unwrap();
+      system.insert_column(4).unwrap();
+
+      let open = format!("{:?}", system);
+      assert!(open.contains("closed: false"));
+      assert!(open.contains("columns: [3, 4]"));
+      assert!(open.contains("coeff: 2, minSize: 1, maxSize: 3"));
+      assert!(!open.contains("row permute"));
+
+      assert!(system.solve());
+      let closed = format!("{:?}", system);
+      assert!(closed.contains("closed: true"));
+      assert!(closed.contains("row permute: [0, 1]"));
+    }
+
+    #[test]
+    fn row_merging_test() {
+      use std::collections::HashSet;
+
+      let build = |merge| {
+        let mut system = DiophantineSystem::new(5, 3);
+        system.insert_row(2, 0, 3).unwrap();
+        system.insert_row(1, 1, 4).unwrap();
+        system.insert_row(2, 0, 3).unwrap();
+        system.insert_row(1, 1, 4).unwrap();
+        system.insert_row(2, 0, 3).unwrap();
+        system.insert_column(7).unwrap();
+        system.insert_column(4).unwrap();
+        system.insert_column(5).unwrap();
+        system.set_row_merging(merge).unwrap();
+        system
+      };
+
+      let naive: Vec<Solution>  = build(false).solutions().collect();
+      let mut system = build(true);
+      let mut merged = Vec::new();
+      system.for_each_solution(|system| {
+        assert!(system.verify_current_solution());
+        merged.push(Solution::new(5, 3, system.solution_flat()));
+        true
+      });
+      assert!(naive.len() > 1);
+      assert_eq!(merged.len(), naive.len());
+      let naive: HashSet<Solution> = naive.into_iter().collect();
+      let merged_set: HashSet<Solution> = merged.iter().cloned().collect();
+      assert_eq!(merged_set, naive);
+
+      // The order is deterministic.
+      let mut again = build(true);
+      assert_eq!(again.solutions().collect::<Vec<Solution>>(), merged);
+      again.reset();
+      assert_eq!(again.solutions().collect::<Vec<Solution>>(), merged);
+      assert_eq!(again.set_row_merging(false), Err(DiophantineError::SystemClosed));
+    }
+
+    #[test]
+    fn stats_test() {
+      let mut system = DiophantineSystem::new(3, 3);
+      system.insert_row(3, 0, 4).unwrap();
+      system.insert_row(2, 0, 4).unwrap();
+      system.insert_row(1, 1, 3).unwrap();
+      system.insert_column(6).unwrap();
+      system.insert_column(5).unwrap();
+      system.insert_column(4).unwrap();
+      assert_eq!(system.stats(), SolveStats::default());
+
+      assert!(system.solve());
+      let first = system.stats();
+      assert_eq!(first.solutions_found, 1);
+      // Both non-last rows were visited at least once.
+      assert!(first.nodes_visited >= 2);
+
+      assert_eq!(system.count_solutions(), 16);
+      let last = system.stats();
+      assert_eq!(last.solutions_found, 17);
+      assert!(last.nodes_visited > first.nodes_visited);
+      assert!(last.backtracks > 0);
+
+      system.reset();
+      assert_eq!(system.stats(), SolveStats::default());
+    }
+
+    #[test]
+    fn solution_limit_test() {
+      let build = |limit| {
+        let mut system = DiophantineSystem::new(3, 3);
+        system.insert_row(3, 0, 4).unwrap();
+        system.insert_row(2, 0, 4).unwrap();
+        system.insert_row(1, 1, 3).unwrap();
+        system.insert_column(6).unwrap();
+        system.insert_column(5).unwrap();
+        system.insert_column(4).unwrap();
+        system.set_solution_limit(limit).unwrap();
+        system
+      };
+
+      assert_eq!(build(None).count_solutions(), 17);
+      assert_eq!(build(Some(0)).count_solutions(), 0);
+      assert_eq!(build(Some(20)).count_solutions(), 17);
+
+      // "Are there at least 5 solutions?"
+      let mut system = build(Some(5));
+      let limited: Vec<Solution> = system.solutions().collect();
+      assert_eq!(limited.len(), 5);
+      assert_eq!(limited[..], build(None).solutions().take(5).collect::<Vec<Solution>>()[..]);
+      assert_eq!(system.set_solution_limit(None), Err(DiophantineError::SystemClosed));
+
+      system.reset();
+      assert_eq!(system.count_solutions(), 5);
+    }
+
+    #[test]
+    fn extension_row_test() {
+      let mut system = DiophantineSystem::new(3, 3);
+      system.insert_row(1, 0, 4).unwrap();
+      system.insert_row(1, 0, 4).unwrap();
+      system.insert_row(2, 0, 4).unwrap();
+      for &column in [2, 3, 4].iter() {
+        system.insert_column(column).unwrap();
+      }
+      assert_eq!(system.count_solutions(), 98);
+
+      system.reset();
+      assert_eq!(
+        system.set_extension_row(3),
+        Err(DiophantineError::IndexOutOfRange { row: 3, col: 0, row_count: 3, col_count: 3 })
+      );
+      system.set_extension_row(1).unwrap();
+      let mut found = 0;
+      system.for_each_solution(|system| {
+        assert!(system.verify_current_solution());
+        let matrix = system.solution_matrix();
+        assert!(matrix[0].iter().sum::<u32>() > 0);
+        assert!(matrix[2].iter().sum::<u32>() > 0);
+        found += 1;
+        true
+      });
+      assert_eq!(found, 94);
+      assert_eq!(system.row_min_size(0), 0);
+      assert_eq!(system.set_extension_row(0), Err(DiophantineError::SystemClosed));
+
+      let mut rebuilt = system.spec().to_system().unwrap();
+      rebuilt.set_row_merging(true).unwrap();
+      assert_eq!(rebuilt.count_solutions(), 94);
+
+      // A row other than the extension row that must be empty.
+      let mut system = DiophantineSystem::new(2, 1);
+      system.insert_row(1, 0, 0).unwrap();
+      system.insert_row(1, 0, 4).unwrap();
+      system.insert_column(3).unwrap();
+      system.set_extension_row(1).unwrap();
+      assert!(!system.solve());
+    }
+
+    #[test]
+    fn builder_test() {
+      let mut system = DiophantineSystemBuilder::new()
+        .row(2, 1, 3)
+        .row(1, 1, 5)
+        .column(3)
+        .column(4)
+        .build()
+        .unwrap();
+      assert_eq!(system.count_solutions(), 5);
+
+      let error = DiophantineSystemBuilder::new().row(0, 1, 3).row(1, 1, 5).column(0).build();
+      assert_eq!(error.unwrap_err(), DiophantineError::InvalidRow);
+
+      // Too little and too much for the rows to take.
+      let small = DiophantineSystemBuilder::new().row(2, 2, 3).row(1, 1, 5).column(3).build();
+      assert_eq!(small.unwrap_err(), DiophantineError::Infeasible);
+      let large = DiophantineSystemBuilder::new().row(2, 1, 1).row(1, 1, 5).column(9).build();
+      assert_eq!(large.unwrap_err(), DiophantineError::Infeasible);
+      let unbounded = DiophantineSystemBuilder::new().row(2, 1, 1).row(1, 1, UNBOUNDED).column(9).build();
+      assert!(unbounded.is_ok());
+
+      let mut system = DiophantineSystemBuilder::<u64>::default().row(1, 0, 4).column(3).build().unwrap();
+      assert_eq!(system.count_solutions(), 1);
+    }
+
+    #[test]
+    fn cell_max_test() {
+      let complex = |bounds: &[(usize, usize, u32)]| {
+        let mut system = DiophantineSystem::new(3, 3);
+        system.insert_row(3, 0, 4).unwrap();
+        system.insert_row(2, 0, 4).unwrap();
+        system.insert_row(1, 1, 3).unwrap();
+        system.insert_column(6).unwrap();
+        system.insert_column(5).unwrap();
+        system.insert_column(4).unwrap();
+        for &(row, col, max) in bounds.iter() {
+          system.set_cell_max(row, col, max).unwrap();
+        }
+        let mut found = 0;
+        system.for_each_solution(|system| {
+          assert!(system.verify_current_solution());
+          for &(row, col, max) in bounds.iter() {
+            assert!(system.solution_unchecked(row, col) <= max);
+          }
+          found += 1;
+          true
+        });
+        found
+      };
+      assert_eq!(complex(&[]), 17);
+      assert_eq!(complex(&[(0, 0, 1)]), 9);
+      assert_eq!(complex(&[(0, 0, 1), (2, 1, 1)]), 7);
+      assert_eq!(complex(&[(1, 2, 0)]), 8);
+
+      // A bound below the greatest amount the solubility vector allows must still leave the later rows a soluble value.
+      let mut system = DiophantineSystem::new(4, 3);
+      for &(coeff, min_size, max_size) in [(3, 1, UNBOUNDED), (3, 0, UNBOUNDED), (4, 0, UNBOUNDED), (2, 2, 5)].iter() {
+        system.insert_row(coeff, min_size, max_size).unwrap();
+      }
+      for &column in [7, 8, 7].iter() {
+        system.insert_column(column).unwrap();
+      }
+      system.set_cell_max(1, 1, 1).unwrap();
+      assert_eq!(system.count_solutions(), 45);
+
+      let mut system = DiophantineSystem::new(3, 3);
+      system.insert_row(1, 0, 4).unwrap();
+      system.insert_row(1, 0, 4).unwrap();
+      system.insert_row(1, 1, 9).unwrap();
+      for &column in [3, 2, 1].iter() {
+        system.insert_column(column).unwrap();
+      }
+      assert_eq!(
+        system.set_cell_max(3, 0, 1),
+        Err(DiophantineError::IndexOutOfRange { row: 3, col: 0, row_count: 3, col_count: 3 })
+      );
+      system.set_cell_max(1, 0, 1).unwrap();
+      system.set_cell_max(2, 1, 0).unwrap();
+      system.set_row_merging(true).unwrap();
+      assert_eq!(system.count_solutions(), 49);
+      assert_eq!(system.spec().cell_max, vec![(1, 0, 1), (2, 1, 0)]);
+      assert_eq!(system.spec().to_system().unwrap().count_solutions(), 49);
+      assert_eq!(system.set_cell_max(0, 0, 1), Err(DiophantineError::SystemClosed));
+    }
+
+    #[test]
+    fn fix_cell_test() {
+      let complex = |fixed: &[(usize, usize, u32)], bounds: &[(usize, usize, u32)]| {
+        let mut system = DiophantineSystem::new(3, 3);
+        system.insert_row(3, 0, 4).unwrap();
+        system.insert_row(2, 0, 4).unwrap();
+        system.insert_row(1, 1, 3).unwrap();
+        system.insert_column(6).unwrap();
+        system.insert_column(5).unwrap();
+        system.insert_column(4).unwrap();
+        for &(row, col, value) in fixed.iter() {
+          system.fix_cell(row, col, value).unwrap();
+        }
+        for &(row, col, max) in bounds.iter() {
+          system.set_cell_max(row, col, max).unwrap();
+        }
+        let mut found = 0;
+        system.for_each_solution(|system| {
+          assert!(system.verify_current_solution());
+          for &(row, col, value) in fixed.iter() {
+            assert_eq!(system.solution_unchecked(row, col), value);
+          }
+          found += 1;
+          true
+        });
+        found
+      };
+      assert_eq!(complex(&[(0, 0, 1)], &[]), 6);
+      assert_eq!(complex(&[(0, 0, 1), (2, 1, 1)], &[]), 1);
+      assert_eq!(complex(&[(2, 2, 2)], &[]), 3);
+      assert_eq!(complex(&[(2, 2, 2)], &[(1, 1, 1)]), 2);
+      // Inconsistent with a column, a row bound, and a cell bound.
+      assert_eq!(complex(&[(0, 0, 3)], &[]), 0);
+      assert_eq!(complex(&[(2, 0, 4)], &[]), 0);
+      assert_eq!(complex(&[(2, 0, 2)], &[(2, 0, 1)]), 0);
+
+      let mut system = DiophantineSystem::new(3, 3);
+      system.insert_row(1, 1, 4).unwrap();
+      system.insert_row(1, 1, 4).unwrap();
+      system.insert_row(1, 1, 9).unwrap();
+      for &column in [3, 2, 1].iter() {
+        system.insert_column(column).unwrap();
+      }
+      assert_eq!(
+        system.fix_cell(0, 3, 1),
+        Err(DiophantineError::IndexOutOfRange { row: 0, col: 3, row_count: 3, col_count: 3 })
+      );
+      system.fix_cell(1, 0, 1).unwrap();
+      system.fix_cell(2, 1, 0).unwrap();
+      assert_eq!(system.count_solutions(), 19);
+      assert_eq!(system.spec().fixed, vec![(1, 0, 1), (2, 1, 0)]);
+      assert_eq!(system.row_max_size(2), 9);
+
+      system.reset();
+      assert_eq!(system.count_solutions(), 19);
+      assert_eq!(system.spec().to_system().unwrap().count_solutions(), 19);
+    }
+
+    #[test]
+    fn signed_system_test() {
+      let solutions = |rows: &[(i64, i64, i64)], columns: &[i64]| {
+        let mut system = SignedDiophantineSystem::new(rows.len(), columns.len());
+        for &(coeff, min_size, max_size) in rows.iter() {
+          system.insert_row(coeff, min_size, max_size).unwrap();
+        }
+        for &column in columns.iter() {
+          system.insert_column(column).unwrap();
+        }
+        let mut found = Vec::new();
+        while system.solve() {
+          let matrix: Vec<Vec<i64>> = (0..rows.len())
+            .map(|r| (0..columns.len()).map(|c| system.solution(r, c).unwrap()).collect())
+            .collect();
+          for (c, &column) in columns.iter().enumerate() {
+            let total: i64 = rows.iter().zip(matrix.iter()).map(|(row, entries)| row.0 * entries[c]).sum();
+            assert_eq!(total, column);
+          }
+          found.push(matrix);
+        }
+        assert_eq!(system.solution(0, 0), Err(DiophantineError::Exhausted));
+        found
+      };
+
+      assert_eq!(solutions(&[(2, 0, 3), (-1, 0, 4), (1, 1, 3)], &[3, -2]).len(), 11);
+      assert_eq!(solutions(&[(3, 0, 3), (-2, 1, 4)], &[1, 4, -2]), vec![vec![vec![1, 2, 0], vec![1, 1, 1]]]);
+      assert_eq!(solutions(&[(1, 0, 4), (-1, 0, 4)], &[0]).len(), 5);
+      assert_eq!(solutions(&[(-1, 2, 2)], &[-1, -1]).len(), 1);
+
+      let mut system = SignedDiophantineSystem::new(1, 1);
+      assert_eq!(system.insert_row(0, 0, 1), Err(DiophantineError::InvalidRow));
+      assert_eq!(system.insert_row(1, 0, i64::UNBOUNDED), Err(DiophantineError::InvalidRow));
+
+      // Products, their sums and the residuals of the columns must fit.
+      assert_eq!(system.insert_row(i64::MAX / 2, 0, 3), Err(DiophantineError::Overflow));
+      system.insert_row(i64::MAX / 2, 0, 1).unwrap();
+      assert_eq!(system.insert_row(i64::MAX / 2, 0, 2), Err(DiophantineError::Overflow));
+      assert_eq!(system.insert_column(i64::MIN + 10), Err(DiophantineError::Overflow));
+      system.insert_column(10).unwrap();
+      assert_eq!(system.insert_row(-(i64::MAX / 2), 0, 2), Err(DiophantineError::Overflow));
+      system.insert_row(-1, 0, 3).unwrap();
+      assert_eq!(system.row_count(), 2);
+      assert!(!system.solve());
+    }
+
+    #[test]
+    fn column_sum_test() {
+      let mut system = DiophantineSystem::new(2, 3);
+      assert_eq!((system.column_sum(), system.max_column_value()), (0, 0));
+      system.insert_row(2, 0, 4).unwrap();
+      system.insert_row(1, 0, 4).unwrap();
+      system.insert_column(3).unwrap();
+      system.insert_column(5).unwrap();
+      system.insert_column(2).unwrap();
+      assert_eq!((system.column_sum(), system.max_column_value()), (10, 5));
+
+      system.fix_cell(0, 1, 2).unwrap();
+      assert!(system.solve());
+      assert_eq!((system.column_sum(), system.max_column_value()), (6, 3));
+      system.reset();
+      assert_eq!((system.column_sum(), system.max_column_value()), (10, 5));
+    }
+
+    #[test]
+    fn classify_test() {
+      let mut system = DiophantineSystem::new(3, 3);
+      system.insert_row(3, 0, 4).unwrap();
+      system.insert_row(2, 0, 4).unwrap();
+      system.insert_row(1, 1, 3).unwrap();
+      system.insert_column(6).unwrap();
+      system.insert_column(5).unwrap();
+      system.insert_column(4).unwrap();
+      assert_eq!(system.classify(), SystemClass::Complex);
+      assert_eq!(system.insert_row(1, 0, 1), Err(DiophantineError::SystemClosed));
+      assert_eq!(system.count_solutions(), 17);
+
+      let mut system = DiophantineSystem::new(2, 2);
+      system.insert_row(1, 0, 4).unwrap();
+      system.insert_row(1, 0, 5).unwrap();
+      system.insert_column(3).unwrap();
+      system.insert_column(5).unwrap();
+      assert_eq!(system.classify(), SystemClass::Simple);
+      assert_eq!(system.classify(), SystemClass::Simple);
+      assert_eq!(system.count_solutions(), 8);
+
+      // The largest `max_size` falls short of a column value.
+      system.reset();
+      system.insert_column(6).unwrap();
+      assert_eq!(system.classify(), SystemClass::Complex);
+
+      // Insoluble, but classified all the same.
+      let mut system = DiophantineSystem::new(2, 1);
+      system.insert_row(2, 2, 4).unwrap();
+      system.insert_row(1, 0, 1).unwrap();
+      system.insert_column(3).unwrap();
+      assert_eq!(system.classify(), SystemClass::Complex);
+      assert!(!system.solve());
+    }
+
+    #[test]
+    fn overflow_test() {
+      let mut system = DiophantineSystem::new(2, 2);
+      assert_eq!(system.insert_row(70_000, 70_000, 70_001), Err(DiophantineError::Overflow));
+      assert_eq!(system.insert_row(70_000, 1, 70_000), Err(DiophantineError::Overflow));
+      system.insert_row(70_000, 1, UNBOUNDED).unwrap();
+      system.insert_column(u32::MAX).unwrap();
+      assert_eq!(system.insert_column(1), Err(DiophantineError::Overflow));
+
+      // The sum of the maximum products overflows, which must not make the system look infeasible.
+      let mut system = DiophantineSystem::new(2, 1);
+      system.insert_row(1, 0, UNBOUNDED).unwrap();
+      system.insert_row(1, 0, 4_000_000_000).unwrap();
+      system.insert_column(4_000_000_000).unwrap();
+      assert!(system.solve());
+      assert!(system.verify_current_solution());
+
+      // Nor may the sum of the minimum products make it look feasible.
+      let mut system = DiophantineSystem::new(2, 1);
+      system.insert_row(1, 3_000_000_000, UNBOUNDED).unwrap();
+      system.insert_row(1, 3_000_000_000, UNBOUNDED).unwrap();
+      system.insert_column(4_000_000_000).unwrap();
+      assert!(!system.solve());
+    }
+
+    #[test]
+    fn solve_nth_test() {
+      let mut system = DiophantineSystem::new(3, 3);
+      system.insert_row(3, 0, 4).unwrap();
+      system.insert_row(2, 0, 4).unwrap();
+      system.insert_row(1, 1, 3).unwrap();
+      system.insert_column(6).unwrap();
+      system.insert_column(5).unwrap();
+      system.insert_column(4).unwrap();
+      let all: Vec<Solution> = system.clone().solutions().collect();
+
+      for &n in [0, 5, 16].iter() {
+        let mut nth = system.clone();
+        assert!(nth.solve_nth(n));
+        let expected: Vec<Vec<u32>> = (0..3).map(|r| all[n as usize].row(r).to_vec()).collect();
+        assert_eq!(nth.solution_matrix(), expected);
+      }
+      assert!(!system.clone().solve_nth(17));
+    }
+
+    #[test]
+    fn from_vectors_test() {
+      let mut system = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]).unwrap();
+      assert_eq!((system.row_count(), system.column_count()), (3, 3));
+      assert_eq!(system.row_coeff(1), 2);
+      assert_eq!(system.count_solutions(), 17);
+
+      let error = DiophantineSystem::from_vectors(&[(2, 3, 1)], &[4]);
+      assert_eq!(error.unwrap_err(), DiophantineError::InvalidRow);
+    }
+
+    #[test]
+    fn row_permutation_test() {
+      let mut system = DiophantineSystem::from_vectors(&[(3, 0, 4), (1, 1, 3), (2, 0, 4)], &[6, 5, 4]).unwrap();
+      assert!(system.row_permutation().is_empty());
+      assert!(system.solve());
+      assert_eq!(system.row_permutation(), &[0, 2, 1]);
+      for (i, &position) in system.row_permutation().iter().enumerate() {
+        assert_eq!(system.row_coeff(i), [3, 2, 1][position as usize]);
+      }
+      system.reset();
+      assert!(system.row_permutation().is_empty());
+    }
+
+    #[test]
+    fn node_budget_test() {
+      let rows = [(3, 0, 4), (2, 0, 4), (1, 1, 3)];
+      let mut system = DiophantineSystem::from_vectors(&rows, &[6, 5, 4]).unwrap();
+      system.set_node_budget(Some(1000)).unwrap();
+      let mut count = 0;
+      while system.try_solve() == Ok(true) {
+        count += 1;
+      }
+      assert_eq!(count, 17);
+      assert!(!system.interrupted());
+
+      system.reset();
+      system.set_node_budget(Some(0)).unwrap();
+      assert_eq!(system.try_solve(), Err(DiophantineError::Interrupted));
+      assert!(system.interrupted());
+      assert_eq!(system.try_solve(), Err(DiophantineError::Interrupted));
+      assert_eq!(system.set_node_budget(None), Err(DiophantineError::SystemClosed));
+
+      // The budget carries over to the merged system.
+      let mut system = DiophantineSystem::from_vectors(&[(1, 0, 4), (1, 0, 4), (1, 1, 9)], &[3, 2, 1]).unwrap();
+      system.set_row_merging(true).unwrap();
+      system.set_node_budget(Some(0)).unwrap();
+      assert!(!system.solve());
+      assert!(system.interrupted());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn par_count_solutions_test() {
+      let check = |rows: &[(u32, u32, u32)], columns: &[u32]| {
+        let mut system = DiophantineSystem::from_vectors(rows, columns).unwrap();
+        let count = system.par_count_solutions();
+        assert!(system.solve());
+        assert_eq!(system.par_count_solutions(), count);
+        assert_eq!(system.count_solutions() + 1, count);
+      };
+      check(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]);
+      check(&[(1, 0, 4), (1, 0, 4), (1, 1, 9)], &[3, 0, 2, 1]);
+      check(&[(2, 1, 3), (1, 1, 5)], &[3, 4]);
+
+      let mut system = DiophantineSystem::from_vectors(&[(2, 0, 9), (1, 1, 3), (1, 0, 9)], &[4, 7, 5]).unwrap();
+      system.set_extension_row(2).unwrap();
+      system.set_cell_max(1, 1, 1).unwrap();
+      assert_eq!(system.par_count_solutions(), system.count_solutions());
+    }
+
+    #[test]
+    fn row_size_bounds_test() {
+      let mut system = DiophantineSystem::from_vectors(&[(2, 1, 3), (1, 1, UNBOUNDED)], &[3, 4]).unwrap();
+      assert_eq!(system.row_size_bounds(0), Some((1, 3)));
+      assert_eq!(system.row_size_bounds(1), Some((1, 5)));
+      system.for_each_solution(|system| {
+        let matrix = system.solution_matrix();
+        let sum: u32 = matrix[0].iter().sum();
+        assert!((1..=3).contains(&sum));
+        true
+      });
+
+      // Feasible as a whole, but the first row cannot take anything from columns smaller than its coefficient.
+      let mut system = DiophantineSystem::from_vectors(&[(3, 1, 1), (1, 0, 5)], &[2, 2]).unwrap();
+      assert_eq!(system.row_size_bounds(0), None);
+      assert!(!system.solve());
+
+      // Fixed entries count toward the sum.
+      let mut system = DiophantineSystem::from_vectors(&[(2, 1, 3), (1, 1, 5)], &[3, 4]).unwrap();
+      system.fix_cell(1, 1, 2).unwrap();
+      assert_eq!(system.row_size_bounds(1), Some((2, 5)));
+
+      // The bounds do not depend on how far the search has gone.
+      let mut system = DiophantineSystem::from_vectors(&[(3, 0, 10), (2, 0, 10), (2, 0, 10)], &[7, 9, 8]).unwrap();
+      let bounds: Vec<Option<(u32, u32)>> = (0..3).map(|i| system.row_size_bounds(i)).collect();
+      assert_eq!(bounds, vec![Some((0, 7)), Some((0, 10)), Some((0, 10))]);
+      assert!(system.solve());
+      assert_eq!((0..3).map(|i| system.row_size_bounds(i)).collect::<Vec<_>>(), bounds);
+      while system.solve() {}
+      assert!(system.is_exhausted());
+      assert_eq!((0..3).map(|i| system.row_size_bounds(i)).collect::<Vec<_>>(), bounds);
+    }
+
+    #[test]
+    fn descending_sort_test() {
+      // Searched in ascending coefficient order, the middle row would fail `viable()` and the solution would be lost.
+      let mut system = DiophantineSystem::from_vectors(&[(1, 3, 3), (5, 1, 1), (5, 0, 1)], &[3, 5]).unwrap();
+      assert_eq!(system.count_solutions(), 1);
+    }
+
+    #[test]
+    fn solution_order_test() {
+      // The first solutions of the example in the crate documentation.
+      let mut system = DiophantineSystem::from_vectors(
+        &[(1, 10, 20), (2, 11, 19), (2, 15, 20), (2, 15, 20), (1, 30, 38), (2, 12, 16)],
+        &[26, 28, 32, 25, 41, 26],
+      ).unwrap();
+      let expected = [
+        [
+          0, 0, 0, 1, 19, 0,  1, 10, 0, 0, 0, 0,  0, 4, 13, 0, 0, 0,
+          0, 0, 3, 12, 5, 0,  0, 0, 0, 0, 12, 26,  12, 0, 0, 0, 0, 0,
+        ],
+        [
+          0, 0, 0, 0, 20, 0,  1, 10, 0, 0, 0, 0,  0, 4, 13, 0, 0, 0,
+          0, 0, 3, 12, 5, 0,  0, 0, 0, 1, 11, 26,  12, 0, 0, 0, 0, 0,
+        ],
+        [
+          0, 0, 0, 1, 18, 1,  1, 10, 0, 0, 0, 0,  0, 4, 13, 0, 0, 0,
+          0, 0, 3, 12, 5, 0,  0, 0, 0, 0, 13, 25,  12, 0, 0, 0, 0, 0,
+        ],
+      ];
+      for flat in expected.iter() {
+        assert!(system.solve());
+        assert_eq!(&system.solution_flat()[..], &flat[..]);
+      }
+      assert_eq!(system.row_permutation(), &[4, 1, 2, 3, 5, 0]);
+
+      // Rows but the last in search order run through their sums in increasing order and, for each sum, through
+      // their entries in increasing colexicographic order.
+      let cases = vec![
+        (vec![(3, 0, 4), (2, 0, 4), (1, 1, 3)], vec![6, 5, 4]),
+        (vec![(2, 1, 3), (1, 1, 5), (1, 0, 2)], vec![3, 4, 2]),
+        (vec![(1, 0, 4), (1, 0, 4), (1, 1, 9)], vec![3, 0, 2, 1]),
+      ];
+      for (rows, columns) in cases {
+        let mut system = DiophantineSystem::from_vectors(&rows, &columns).unwrap();
+        let mut previous: Option<Vec<(u32, Vec<u32>)>> = None;
+        system.for_each_solution(|system| {
+          let matrix    = system.solution_matrix();
+          let mut order = (0..rows.len()).collect::<Vec<_>>();
+          order.sort_by_key(|&i| system.row_permutation()[i]);
+          let key = order[..rows.len() - 1].iter().map(|&i| {
+            (matrix[i].iter().sum::<u32>(), matrix[i].iter().rev().cloned().collect::<Vec<_>>())
+          }).collect::<Vec<_>>();
+          if let Some(previous) = previous.as_ref() {
+            assert!(previous < &key);
+          }
+          previous = Some(key);
+          true
+        });
+        assert!(previous.is_some());
+      }
+    }
+
+    #[test]
+    fn solve_lex_test() {
+      let build = |rows: &[(u32, u32, u32)], columns: &[u32]| DiophantineSystem::from_vectors(rows, columns).unwrap();
+      let mut systems = vec![
+        build(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]),
+        build(&[(1, 2, 4), (2, 1, 3), (1, 1, 5)], &[3, 4, 2]),
+        build(&[(1, 0, 4), (1, 0, 4), (1, 1, 9)], &[3, 0, 2, 1]),
+        build(&[(1, 3, 3), (5, 1, 1), (5, 0, 1)], &[3, 5]),
+        build(&[(2, 1, 3)], &[4, 2]),
+      ];
+      let mut bounded = build(&[(2, 0, 4), (1, 0, 4), (1, 1, 9)], &[6, 3, 5]);
+      bounded.set_cell_max(2, 0, 1).unwrap();
+      bounded.fix_cell(1, 2, 1).unwrap();
+      systems.push(bounded);
+
+      for system in systems {
+        let expected = system.clone().count_solutions();
+        let mut system = system;
+        let mut previous: Option<Vec<u32>> = None;
+        let mut count = 0;
+        while system.solve_lex() {
+          let flat = system.solution_flat();
+          if let Some(previous) = previous.as_ref() {
+            assert!(previous < &flat);
+          }
+          previous = Some(flat);
+          count += 1;
+        }
+        assert!(count > 0);
+        assert_eq!(count, expected);
+      }
+    }
+
+    #[test]
+    fn state_predicates_test() {
+      let mut system = DiophantineSystem::from_vectors(&[(2, 1, 3), (1, 1, 5)], &[3, 4]).unwrap();
+      assert!(!system.has_current_solution() && !system.is_exhausted());
+      system.classify();
+      assert!(!system.has_current_solution() && !system.is_exhausted());
+      while system.solve() {
+        assert!(system.has_current_solution() && !system.is_exhausted());
+      }
+      assert!(!system.has_current_solution() && system.is_exhausted());
+      system.reset();
+      assert!(!system.has_current_solution() && !system.is_exhausted());
+
+      let mut system = DiophantineSystem::from_vectors(&[(3, 1, 1), (1, 0, 5)], &[2, 2]).unwrap();
+      assert!(!system.solve());
+      assert!(system.is_exhausted());
+
+      let mut system = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]).unwrap();
+      system.set_node_budget(Some(0)).unwrap();
+      assert!(!system.solve());
+      assert!(system.interrupted() && !system.is_exhausted() && !system.has_current_solution());
+    }
+
+    #[test]
+    fn set_column_test() {
+      let mut system = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 9, 7]).unwrap();
+      assert_eq!(
+        system.set_column(3, 5),
+        Err(DiophantineError::IndexOutOfRange { row: 0, col: 3, row_count: 3, col_count: 3 })
+      );
+      system.set_column(1, 5).unwrap();
+      system.set_column(2, 4).unwrap();
+      assert_eq!((system.column_sum(), system.max_column_value()), (15, 6));
+      assert_eq!(system.count_solutions(), 17);
+      assert_eq!(system.set_column(0, 1), Err(DiophantineError::SystemClosed));
+
+      // Sweep the right-hand side of the same rows.
+      system.reset();
+      system.set_column(0, 3).unwrap();
+      assert_eq!(system.max_column_value(), 5);
+      let mut fresh = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[3, 5, 4]).unwrap();
+      let expected  = fresh.count_solutions();
+      assert!(expected > 0);
+      assert_eq!(system.count_solutions(), expected);
+    }
+
+    #[test]
+    fn with_new_columns_test() {
+      let rows = [(3, 0, 4), (2, 0, 4), (1, 1, 3), (2, 0, UNBOUNDED)];
+      let fresh_count = |columns: &[u32]| DiophantineSystem::from_vectors(&rows, columns).unwrap().count_solutions();
+
+      let mut system = DiophantineSystem::from_vectors(&rows, &[6, 5, 4]).unwrap();
+      assert_eq!(system.classify(), SystemClass::Complex);
+      for columns in [[6, 5, 4], [4, 6, 2], [1, 0, 3], [9, 8, 7], [6, 6, 6]].iter() {
+        let mut next = system.with_new_columns(columns).unwrap();
+        assert_eq!(next.count_solutions(), fresh_count(columns));
+        let mut lex   = system.with_new_columns(columns).unwrap();
+        let mut count = 0;
+        while lex.solve_lex() {
+          count += 1;
+        }
+        assert_eq!(count, fresh_count(columns));
+      }
+      assert!(system.solve());
+
+      let mut fixed = system.with_new_columns(&[5, 6, 2]).unwrap();
+      fixed.fix_cell(0, 1, 1).unwrap();
+      let mut expected = DiophantineSystem::from_vectors(&rows, &[5, 6, 2]).unwrap();
+      expected.fix_cell(0, 1, 1).unwrap();
+      assert_eq!(fixed.count_solutions(), expected.count_solutions());
+
+      assert_eq!(system.with_new_columns(&[1, 2, u32::MAX]).unwrap_err(), DiophantineError::Overflow);
+      assert_eq!(
+        system.with_new_columns(&[1, 2]).unwrap_err(),
+        DiophantineError::ColumnCountMismatch { expected: 3, found: 2 }
+      );
+    }
+
+    #[test]
+    fn single_row_test() {
+      // Simple: the row takes the columns as they are.
+      let mut system = DiophantineSystem::from_vectors(&[(1, 2, 9)], &[3, 0, 4]).unwrap();
+      assert_eq!(system.classify(), SystemClass::Simple);
+      assert!(system.solve());
+      assert_eq!(system.solution_flat(), vec![3, 0, 4]);
+      assert!(!system.solve());
+      assert!(system.is_exhausted());
+
+      // Complex: every column must be a multiple of the coefficient.
+      let mut system = DiophantineSystem::from_vectors(&[(2, 1, 3)], &[4, 2]).unwrap();
+      assert_eq!(system.classify(), SystemClass::Complex);
+      assert!(system.solve());
+      assert_eq!(system.solution_flat(), vec![2, 1]);
+      assert!(!system.solve());
+      assert!(system.is_exhausted());
+
+      let cases = [
+        ((1, 0, 6), vec![3, 4]),  // sum above max_size
+        ((1, 8, 9), vec![3, 4]),  // sum below min_size
+        ((2, 0, 9), vec![4, 3]),  // column not a multiple of the coefficient
+        ((2, 0, 2), vec![4, 2]),  // sum above max_size
+        ((3, 3, 9), vec![3, 3]),  // sum below min_size
+      ];
+      for &(row, ref columns) in cases.iter() {
+        let mut system = DiophantineSystem::from_vectors(&[row], columns).unwrap();
+        assert!(!system.solve());
+        assert!(!system.solve());
+        assert!(system.is_exhausted());
+      }
+
+      // Bounds on entries are checked on the only row too.
+      let mut system = DiophantineSystem::from_vectors(&[(1, 0, 9)], &[3, 4]).unwrap();
+      system.set_cell_max(0, 1, 3).unwrap();
+      assert!(!system.solve());
+      assert!(system.is_exhausted());
+    }
+
+    #[test]
+    fn empty_system_test() {
+      let cases = vec![
+        (vec![], vec![], 1),
+        (vec![], vec![0, 0], 1),
+        (vec![], vec![0, 3], 0),
+        (vec![(2, 0, 3), (1, 0, UNBOUNDED)], vec![], 1),
+        (vec![(2, 0, 3), (1, 1, 5)], vec![], 0),
+      ];
+      for (rows, columns, expected) in cases {
+        let mut system = DiophantineSystem::from_vectors(&rows, &columns).unwrap();
+        assert_eq!(system.classify(), SystemClass::Simple);
+        assert_eq!(system.solve(), expected == 1);
+        if expected == 1 {
+          assert_eq!(system.solution_matrix(), vec![Vec::<u32>::new(); rows.len()]);
+          assert!(!system.solve());
+        }
+        assert!(system.is_exhausted());
+        system.reset();
+        assert_eq!(system.count_solutions(), expected);
+        #[cfg(feature = "std")]
+        assert_eq!(system.par_count_solutions(), expected);
+
+        let mut signed = SignedDiophantineSystem::new(rows.len(), columns.len());
+        for &(coeff, min_size, max_size) in rows.iter() {
+          let max_size = if max_size == UNBOUNDED { 9 } else { max_size };
+          signed.insert_row(coeff as i64, min_size as i64, max_size as i64).unwrap();
+        }
+        for &column in columns.iter() {
+          signed.insert_column(column as i64).unwrap();
+        }
+        assert_eq!(signed.solve(), expected == 1);
+        assert!(!signed.solve());
+      }
+
+      // Every row but the extension row must be nonempty, which it cannot be without columns.
+      let mut system = DiophantineSystem::from_vectors(&[(2, 0, 3), (1, 0, 5)], &[]).unwrap();
+      system.set_extension_row(1).unwrap();
+      assert!(!system.solve());
+    }
+
+    #[test]
+    fn solubility_test() {
+      let mut system = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]).unwrap();
+      assert!(system.solubility(0).is_none());
+      assert_eq!(system.classify(), SystemClass::Complex);
+
+      // The last row in search order takes all of a column, up to its max_size.
+      let last = system.solubility(2).unwrap();
+      assert_eq!(last.len(), 7);
+      for (v, entry) in last.iter().enumerate() {
+        assert_eq!(entry.is_soluble(), v <= 3);
+        if entry.is_soluble() {
+          assert_eq!((entry.min, entry.max), (v as u32, v as u32));
+        } else {
+          assert_eq!(*entry, Soluble { min: Soluble::<u32>::INSOLUBLE, max: Soluble::<u32>::INSOLUBLE });
+        }
+      }
+      // The row with coefficient 2 must take enough of a column to leave at most 3.
+      let middle = system.solubility(1).unwrap();
+      assert_eq!((middle[5].min, middle[5].max), (1, 2));
+      assert_eq!((middle[6].min, middle[6].max), (2, 3));
+
+      let mut simple = DiophantineSystem::from_vectors(&[(2, 1, 3), (1, 1, 5)], &[3, 4]).unwrap();
+      assert_eq!(simple.classify(), SystemClass::Simple);
+      assert!(simple.solubility(0).is_none());
+    }
+
+    #[test]
+    fn first_solution_test() {
+      let mut system = DiophantineSystem::from_vectors(&[(2, 1, 3), (1, 1, 5)], &[3, 4]).unwrap();
+      let first = system.first_solution().unwrap();
+      assert!(system.row_permutation().is_empty() && !system.has_current_solution());
+      assert!(system.solve());
+      assert_eq!(system.solution_matrix(), first);
+      assert!(system.solve());
+      assert_eq!(system.first_solution().unwrap(), first);
+      assert_ne!(system.solution_matrix(), first);
+      assert_eq!(system.count_solutions(), 3);
+
+      let system = DiophantineSystem::from_vectors(&[(2, 1, 3)], &[3]).unwrap();
+      assert_eq!(system.first_solution(), None);
+    }
+
+    #[test]
+    fn infeasible_column_test() {
+      // Rows with even coefficients cannot make up the odd column.
+      let mut system = DiophantineSystem::from_vectors(&[(4, 0, 5), (2, 0, 5)], &[8, 6, 7, 2]).unwrap();
+      assert_eq!(system.infeasible_column(), None);
+      assert!(!system.solve());
+      assert_eq!(system.infeasible_column(), Some(2));
+      system.reset();
+      assert_eq!(system.infeasible_column(), None);
+
+      let mut system = DiophantineSystem::from_vectors(&[(4, 0, 5), (2, 0, 5)], &[8, 6, 4, 2]).unwrap();
+      assert!(system.solve());
+      assert_eq!(system.infeasible_column(), None);
+    }
+
+    #[test]
+    fn division_test() {
+      let cases: [(i64, i64, i64, i64); 12] = [
+        // (dividend, divisor, ceiling, floor)
+        (7, 2, 4, 3),
+        (8, 2, 4, 4),
+        (0, 3, 0, 0),
+        (-7, 2, -3, -4),
+        (-8, 2, -4, -4),
+        (7, -2, -3, -4),
+        (-7, -2, 4, 3),
+        (-8, -2, 4, 4),
+        (0, -3, 0, 0),
+        (1, 5, 1, 0),
+        (-1, 5, 0, -1),
+        (i64::MIN, 2, i64::MIN / 2, i64::MIN / 2),
+      ];
+      for &(dividend, divisor, ceiling, floor) in cases.iter() {
+        assert_eq!(ceiling_division(dividend, divisor), ceiling, "{} / {}", dividend, divisor);
+        assert_eq!(floor_division(dividend, divisor), floor, "{} / {}", dividend, divisor);
+      }
+
+      // The extremes of unsigned types, where adding `divisor - 1` first would overflow.
+      assert_eq!(ceiling_division(u32::MAX, 2), u32::MAX / 2 + 1);
+      assert_eq!(floor_division(u32::MAX, 2), u32::MAX / 2);
+      assert_eq!(ceiling_division(u32::MAX, u32::MAX), 1);
+      assert_eq!(ceiling_division(u32::MAX - 1, u32::MAX), 1);
+      assert_eq!(floor_division(u32::MAX - 1, u32::MAX), 0);
+      assert_eq!(ceiling_division(i64::MAX, -1), -i64::MAX);
+    }
+
+    #[test]
+    fn count_solutions_canonical_test() {
+      // Rows 0 and 2 are interchangeable.
+      let mut system = DiophantineSystem::from_vectors(&[(2, 0, 3), (1, 1, 4), (2, 0, 3)], &[4, 3, 5]).unwrap();
+      assert_eq!(system.clone().count_solutions(), 37);
+      assert_eq!(system.count_solutions_canonical(), 19);
+
+      // Three interchangeable rows, with and without row merging.
+      let rows  = [(1, 0, 3), (1, 0, 3), (1, 0, 3), (2, 1, 2)];
+      let build = || DiophantineSystem::from_vectors(&rows, &[3, 2, 4]).unwrap();
+      assert_eq!(build().count_solutions(), 471);
+      assert_eq!(build().count_solutions_canonical(), 89);
+      let mut merged = build();
+      merged.set_row_merging(true).unwrap();
+      assert_eq!(merged.count_solutions_canonical(), 89);
+
+      // A bound on an entry makes a row distinct, leaving rows 0 and 2 interchangeable.
+      let mut bounded = build();
+      bounded.set_cell_max(1, 1, 1).unwrap();
+      let mut expected = 0;
+      build().for_each_solution(|system| {
+        let row = |r| (0..3).map(|c| system.solution_unchecked(r, c)).collect::<Vec<_>>();
+        if system.solution_unchecked(1, 1) <= 1 && row(0) <= row(2) {
+          expected += 1;
+        }
+        true
+      });
+      assert_eq!(bounded.count_solutions_canonical(), expected);
+    }
+
+    #[test]
+    fn column_sorting_test() {
+      let build = |sort| {
+        let mut system = DiophantineSystem::from_vectors(&[(2, 0, 4), (1, 0, 4), (1, 1, 9)], &[3, 6, 0, 5]).unwrap();
+        system.set_cell_max(2, 1, 2).unwrap();
+        system.fix_cell(1, 3, 1).unwrap();
+        system.set_column_sorting(sort).unwrap();
+        system
+      };
+      let collect = |mut system: DiophantineSystem| {
+        let mut solutions = Vec::new();
+        system.for_each_solution(|system| {
+          assert!(system.verify_current_solution());
+          assert_eq!(system.solution(1, 3), Ok(1));
+          assert!(system.solution(2, 1).unwrap() <= 2);
+          solutions.push(system.solution_flat());
+          true
+        });
+        solutions.sort();
+        solutions
+      };
+
+      let mut sorted = build(true);
+      assert!(sorted.solve());
+      assert_eq!(sorted.column_permutation(), &[2, 0, 3, 1]);
+      assert_eq!(sorted.spec(), build(false).spec());
+      let expected = collect(build(false));
+      assert!(!expected.is_empty());
+      assert_eq!(collect(build(true)), expected);
+      sorted.reset();
+      assert!(sorted.column_permutation().is_empty());
+      assert_eq!(collect(sorted), expected);
+
+      // The insoluble column is reported by its insertion index.
+      let mut system = DiophantineSystem::from_vectors(&[(4, 0, 5), (2, 0, 5)], &[2, 8, 7]).unwrap();
+      system.set_column_sorting(true).unwrap();
+      assert!(!system.solve());
+      assert_eq!(system.infeasible_column(), Some(2));
+    }
+
+    #[test]
+    fn solution_row_column_test() {
+      let mut system = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]).unwrap();
+      system.set_column_sorting(true).unwrap();
+      system.for_each_solution(|system| {
+        let matrix = system.solution_matrix();
+        for (r, row) in matrix.iter().enumerate() {
+          assert_eq!(&system.solution_row(r), row);
+        }
+        for c in 0..3 {
+          assert_eq!(system.solution_column(c), matrix.iter().map(|row| row[c]).collect::<Vec<_>>());
+        }
+        true
+      });
+    }
+
+    #[test]
+    fn solution_assignments_test() {
+      let build = || DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 0, 5, 4]).unwrap();
+      let mut expected = Vec::new();
+      build().for_each_solution(|system| {
+        expected.push(system.solution_nonzero());
+        true
+      });
+
+      let mut system = build();
+      let assignments: Vec<_> = system.solution_assignments().collect();
+      assert_eq!(assignments.len(), 17);
+      assert_eq!(assignments, expected);
+      for assignment in assignments.iter() {
+        assert!(assignment.iter().all(|&(r, c, value)| r < 3 && c != 1 && value > 0));
+      }
+      assert_eq!(system.solution_assignments().next(), None);
+    }
+
+    #[test]
+    fn estimated_solution_count_test() {
+      let cases = vec![
+        (vec![(3, 0, 4), (2, 0, 4), (1, 1, 3)], vec![6, 5, 4]),
+        (vec![(2, 1, 3), (1, 1, 5)], vec![3, 4]),
+        (vec![(1, 0, 4), (1, 0, 4), (1, 1, 9)], vec![3, 0, 2, 1]),
+        (vec![(1, 10, 20), (2, 11, 19), (2, 15, 20), (1, 30, 38)], vec![16, 18, 22, 15]),
+        (vec![(2, 1, 3)], vec![4, 2]),
+        (vec![(2, 3, 3), (1, 1, 1)], vec![2, 2]),
+      ];
+      for (rows, columns) in cases {
+        let mut system = DiophantineSystem::from_vectors(&rows, &columns).unwrap();
+        let estimate   = system.estimated_solution_count();
+        assert!(system.row_permutation().is_empty());
+        assert!(estimate >= system.count_solutions());
+      }
+
+      // Two rows: the estimate counts the selections of the row with fewer, here the second row taking a sum of 1 or
+      // 2 from two columns.
+      let system = DiophantineSystem::from_vectors(&[(1, 0, 9), (1, 1, 2)], &[5, 5]).unwrap();
+      assert_eq!(system.estimated_solution_count(), 5);
+      let mut system = DiophantineSystem::from_vectors(&[(1, 0, 9), (1, 1, 2)], &[5, 5]).unwrap();
+      system.set_cell_max(1, 0, 0).unwrap();
+      assert_eq!(system.estimated_solution_count(), 2);
+
+      // Row sums too large to count exactly fall back to the ranges of the entries, without overflowing.
+      let mut system: DiophantineSystem<u64> = DiophantineSystem::with_capacity(2, 1);
+      system.insert_row(1, 0, u64::UNBOUNDED).unwrap();
+      system.insert_row(1, 0, u64::UNBOUNDED).unwrap();
+      system.insert_column(u64::MAX / 2).unwrap();
+      assert_eq!(system.estimated_solution_count(), u64::MAX / 2 + 1);
+      let mut system: DiophantineSystem<u128> = DiophantineSystem::with_capacity(2, 2);
+      system.insert_row(1, 0, u128::UNBOUNDED).unwrap();
+      system.insert_row(1, 0, u128::UNBOUNDED).unwrap();
+      system.insert_column(u128::MAX / 2).unwrap();
+      system.insert_column(u128::MAX / 2).unwrap();
+      assert_eq!(system.estimated_solution_count(), u64::MAX);
+    }
+
+    #[test]
+    fn push_column_and_resolve_test() {
+      let rows = [(3, 0, 4), (2, 0, 4), (1, 1, 3)];
+      let mut system = DiophantineSystem::from_vectors(&rows, &[6, 5]).unwrap();
+      system.fix_cell(2, 0, 1).unwrap();
+      assert!(system.solve());
+
+      // The first column raises the largest column value; the others let the solubility vectors be reused.
+      for (value, columns) in [(7, vec![6, 5, 7]), (2, vec![6, 5, 7, 2]), (3, vec![6, 5, 7, 2, 3])] {
+        system.push_column_and_resolve(value).unwrap();
+        let mut fresh = DiophantineSystem::from_vectors(&rows, &columns).unwrap();
+        fresh.fix_cell(2, 0, 1).unwrap();
+        let expected = fresh.count_solutions();
+        assert!(system.solve());
+        assert_eq!(system.solution(2, 0), Ok(1));
+        assert_eq!(system.count_solutions() + 1, expected);
+      }
+
+      assert_eq!(system.push_column_and_resolve(u32::MAX), Err(DiophantineError::Overflow));
+      assert_eq!(system.column_count(), 5);
+    }
+
+    #[test]
+    fn iter_solution_cells_test() {
+      let mut system = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]).unwrap();
+      system.set_column_sorting(true).unwrap();
+      let mut count = 0;
+      system.for_each_solution(|system| {
+        let cells: Vec<u32> = system.iter_solution_cells().collect();
+        assert_eq!(cells, system.solution_flat());
+        assert_eq!(cells[5], system.solution_unchecked(1, 2));
+        count += 1;
+        true
+      });
+      assert_eq!(count, 17);
+    }
+
+    #[test]
+    fn solution_key_test() {
+      // The same solutions found by systems with differently ordered searches.
+      let mut plain  = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]).unwrap();
+      let mut sorted = plain.clone();
+      sorted.set_column_sorting(true).unwrap();
+
+      let mut keys      = std::collections::HashSet::new();
+      let mut solutions = std::collections::HashSet::new();
+      plain.for_each_solution(|system| {
+        let solution = system.current_solution();
+        assert_eq!(solution.key(), system.solution_key());
+        keys.insert(system.solution_key());
+        solutions.insert(solution);
+        true
+      });
+      assert_eq!((keys.len(), solutions.len()), (17, 17));
+
+      sorted.for_each_solution(|system| {
+        assert!(keys.contains(&system.solution_key()));
+        assert!(!solutions.insert(system.current_solution()));
+        true
+      });
+      assert_eq!(solutions.len(), 17);
+
+      // Keys depend on the values of the entries, not their type, and entries too wide for a `u64` are not truncated.
+      let key = |column: u128| {
+        let mut system: DiophantineSystem<u128> = DiophantineSystem::with_capacity(1, 1);
+        system.insert_row(1, 0, u128::UNBOUNDED).unwrap();
+        system.insert_column(column).unwrap();
+        assert!(system.solve());
+        system.solution_key()
+      };
+      let mut narrow = DiophantineSystem::from_vectors(&[(1, 0, UNBOUNDED)], &[5]).unwrap();
+      assert!(narrow.solve());
+      assert_eq!(key(5), narrow.solution_key());
+      assert_ne!(key(5), key((1 << 64) + 5));
+      assert_ne!(key(u64::MAX as u128), key((u64::MAX as u128) << 64 | 1));
+    }
+
+    #[test]
+    fn large_min_size_test() {
+      // Rows whose `min_size` alone rules out some or all selections, in complex and simple systems. The counts are
+      // from a brute-force enumeration.
+      type Case = (&'static [(u32, u32, u32)], &'static [u32], u64);
+      let cases: [Case; 9] = [
+        (&[(3, 6, 9), (2, 0, 4), (1, 1, 3)], &[6, 5, 4], 0),
+        (&[(3, 0, 4), (2, 8, 8), (1, 1, 3)], &[6, 5, 4], 0),
+        (&[(3, 0, 4), (2, 5, 5), (1, 1, 3)], &[6, 5, 4], 5),
+        (&[(3, 4, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4], 2),
+        (&[(3, 0, 4), (2, 0, 4), (1, 4, 4)], &[6, 5, 4], 15),
+        (&[(1, 0, 4), (1, 0, 4), (1, 7, 9)], &[3, 0, 2, 1], 0),
+        (&[(1, 0, 4), (1, 5, 9), (1, 1, 9)], &[3, 0, 2, 1], 3),
+        (&[(2, 3, 3), (1, 0, 9)], &[3, 4], 1),
+        (&[(2, 4, 4), (1, 0, 9)], &[3, 4], 0),
+      ];
+      for &(rows, columns, count) in cases.iter() {
+        let mut system = DiophantineSystem::from_vectors(rows, columns).unwrap();
+        assert_eq!(system.try_solve(), Ok(count > 0), "{:?} {:?}", rows, columns);
+        assert_eq!(DiophantineSystem::from_vectors(rows, columns).unwrap().count_solutions(), count);
+      }
+    }
+
+    #[test]
+    fn find_solutions_test() {
+      let build = || DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]).unwrap();
+      let all: Vec<Vec<Vec<u32>>> = build().solutions().map(|solution| {
+        (0..3).map(|r| solution.row(r).to_vec()).collect()
+      }).collect();
+      let expected: Vec<_> = all.iter().filter(|matrix| matrix[2][0] > 0).cloned().collect();
+      assert!(expected.len() > 3);
+
+      let mut system = build();
+      let found = system.find_solutions(3, |system| system.solution_unchecked(2, 0) > 0);
+      assert_eq!(found, expected[..3].to_vec());
+      assert_eq!(system.solution_matrix(), expected[2]);
+      assert_eq!(build().find_solutions(100, |system| system.solution_unchecked(2, 0) > 0), expected);
+      assert_eq!(build().find_solutions(0, |_| true), Vec::<Vec<Vec<u32>>>::new());
+    }
+
+    #[test]
+    fn format_test() {
+      let mut system = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, UNBOUNDED)], &[6, 5, 4]).unwrap();
+      let expected = "coeff | min | max\n    3 |   0 |   4\n    2 |   0 |   4\n    1 |   1 | inf\ncolumns: 6 5 4\n";
+      assert_eq!(system.format_system(), expected);
+      while system.solve() {
+        if system.solution_matrix() == vec![vec![2, 0, 0], vec![0, 1, 0], vec![0, 3, 4]] {
+          break;
+        }
+      }
+      assert_eq!(system.format_solution(), "3 | 2 0 0 |\n2 | 0 1 0 |\n1 | 0 3 4 |\n  = 6 5 4\n");
+
+      let mut system = DiophantineSystem::from_vectors(&[(12, 0, 2), (1, 0, 20)], &[24, 13]).unwrap();
+      assert!(system.solve());
+      assert_eq!(system.format_solution(), "12 |  2  0 |\n 1 |  0 13 |\n   = 24 13\n");
+    }
+
+    #[test]
+    fn row_order_test() {
+      fn ascending_coeff(a: &RowSpec<u32>, b: &RowSpec<u32>) -> core::cmp::Ordering {
+        a.coeff.cmp(&b.coeff)
+      }
+      let orders = [
+        RowOrder::DescCoeff,
+        RowOrder::DescMaxProduct,
+        RowOrder::Custom(ascending_coeff),
+      ];
+      type Case = (&'static [(u32, u32, u32)], &'static [u32]);
+      let cases: [Case; 4] = [
+        (&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]),
+        (&[(2, 1, 3), (1, 1, 5)], &[3, 4]),
+        (&[(1, 0, 4), (1, 0, 4), (1, 1, 9)], &[3, 0, 2, 1]),
+        (&[(1, 1, 2), (2, 0, 9), (5, 1, 1), (2, 0, 9)], &[7, 5, 9]),
+      ];
+      for &(rows, columns) in cases.iter() {
+        let mut system = DiophantineSystem::from_vectors(rows, columns).unwrap();
+        let mut expected: Vec<Solution<u32>> = system.solutions().collect();
+        expected.sort_by_key(|solution| solution.key());
+        for &order in orders.iter() {
+          for &merge in [false, true].iter() {
+            let mut system = DiophantineSystem::from_vectors(rows, columns).unwrap();
+            system.set_row_order(order).unwrap();
+            system.set_row_merging(merge).unwrap();
+            let mut found: Vec<Solution<u32>> = system.solutions().collect();
+            found.sort_by_key(|solution| solution.key());
+            assert_eq!(found, expected, "{:?} {:?} {:?}", rows, order, merge);
+          }
+        }
+      }
+
+      let mut system = DiophantineSystem::from_vectors(&[(3, 0, 1), (1, 0, 9), (2, 0, 9)], &[6, 5, 4]).unwrap();
+      system.set_row_order(RowOrder::DescMaxProduct).unwrap();
+      assert!(system.solve());
+      assert_eq!(system.row_permutation(), &[2, 1, 0]);
+      assert_eq!(system.set_row_order(RowOrder::DescCoeff), Err(DiophantineError::SystemClosed));
+
+      let mut system = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]).unwrap();
+      system.set_row_order(RowOrder::Custom(ascending_coeff)).unwrap();
+      assert!(system.solve());
+      assert_eq!(system.row_permutation(), &[2, 1, 0]);
+    }
+
+    #[test]
+    fn solubility_memory_test() {
+      // Three rows of solubility vectors with entries for the column values 0 to 6.
+      let needed = 3 * 7 * core::mem::size_of::<Soluble<u32>>();
+      let build  = || DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]).unwrap();
+
+      let mut system = build();
+      system.set_max_solubility_memory(Some(needed - 1)).unwrap();
+      assert_eq!(system.try_solve(), Err(DiophantineError::TooLarge));
+      assert!(!system.solve());
+      assert!(!system.is_exhausted());
+      assert_eq!(system.set_max_solubility_memory(None), Err(DiophantineError::SystemClosed));
+      system.reset();
+      assert_eq!(system.try_solve(), Err(DiophantineError::TooLarge));
+
+      let mut system = build();
+      system.set_max_solubility_memory(Some(needed)).unwrap();
+      assert_eq!(system.count_solutions(), 17);
+
+      // Simple systems have no solubility vectors.
+      let mut system = DiophantineSystem::from_vectors(&[(2, 1, 3), (1, 1, 5)], &[3, 4]).unwrap();
+      system.set_max_solubility_memory(Some(0)).unwrap();
+      assert_eq!(system.count_solutions(), 5);
+
+      // The limit applies to the merged system.
+      let mut system = DiophantineSystem::from_vectors(&[(2, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]).unwrap();
+      system.set_row_merging(true).unwrap();
+      system.set_max_solubility_memory(Some(0)).unwrap();
+      assert_eq!(system.try_solve(), Err(DiophantineError::TooLarge));
+    }
+
+    #[test]
+    fn solve_outcome_test() {
+      let mut system = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]).unwrap();
+      let mut count = 0;
+      while system.solve_outcome() == SolveOutcome::Solution {
+        count += 1;
+      }
+      assert_eq!(count, 17);
+      assert_eq!(system.solve_outcome(), SolveOutcome::Exhausted);
+
+      system.reset();
+      system.set_node_budget(Some(0)).unwrap();
+      assert_eq!(system.solve_outcome(), SolveOutcome::Interrupted);
+      assert_eq!(system.solve_outcome(), SolveOutcome::Interrupted);
+    }
+
+    #[test]
+    fn into_iterator_test() {
+      let mut system = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]).unwrap();
+      let expected: Vec<Solution<u32>> = system.clone().solutions().collect();
+      let mut found = Vec::new();
+      for solution in &mut system {
+        found.push(solution);
+      }
+      assert_eq!(found, expected);
+      assert!(system.is_exhausted());
+    }
+
+    #[test]
+    fn is_feasible_test() {
+      let mut system = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]).unwrap();
+      assert!(system.is_feasible());
+      assert!(!system.has_current_solution());
+      assert_eq!(system.count_solutions(), 17);
+      // After the search is exhausted, the answer is for the system as built.
+      assert!(system.is_feasible());
+      assert!(system.is_exhausted());
+
+      let mut system = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]).unwrap();
+      assert!(system.solve());
+      let matrix = system.solution_matrix();
+      assert!(system.is_feasible());
+      assert_eq!(system.solution_matrix(), matrix);
+      assert_eq!(system.count_solutions(), 16);
+
+      let mut system = DiophantineSystem::from_vectors(&[(2, 1, 3), (1, 1, 5)], &[3, 0]).unwrap();
+      system.set_extension_row(1).unwrap();
+      assert!(system.is_feasible());
+      let mut system = DiophantineSystem::from_vectors(&[(3, 1, 4), (2, 1, 4)], &[6, 1, 4]).unwrap();
+      assert!(!system.is_feasible());
+      assert!(!system.is_feasible());
+    }
+
+    #[test]
+    fn solution_base_test() {
+      let mut system = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]).unwrap();
+      assert_eq!(system.solution_base(0, 0), Err(DiophantineError::NoSolutionYet));
+      let mut forced = 0;
+      system.for_each_solution(|system| {
+        for r in 0..3 {
+          for c in 0..3 {
+            let base = system.solution_base(r, c).unwrap();
+            assert_eq!(base + system.solution_extra(r, c).unwrap(), system.solution_unchecked(r, c));
+            if r == 2 {
+              // The row with the least coefficient is searched last.
+              assert_eq!(system.solution_extra(r, c), Ok(0));
+            } else {
+              forced += base;
+            }
+          }
+        }
+        true
+      });
+      assert!(forced > 0);
+      assert_eq!(system.solution_extra(0, 0), Err(DiophantineError::Exhausted));
+
+      // Fixed values are forced, and nothing else is in the first row of a simple system.
+      let mut system = DiophantineSystem::from_vectors(&[(2, 1, 3), (1, 1, 5)], &[3, 4]).unwrap();
+      system.fix_cell(0, 1, 1).unwrap();
+      assert!(system.solve());
+      assert_eq!((system.solution_base(0, 0), system.solution_base(0, 1)), (Ok(0), Ok(1)));
+      assert_eq!(
+        system.solution_base(0, 2),
+        Err(DiophantineError::IndexOutOfRange { row: 0, col: 2, row_count: 2, col_count: 2 })
+      );
+    }
+
+    #[test]
+    fn from_raw_test() {
+      let rows    = vec![(3, 0, 4), (2, 0, 4), (1, 1, 3)];
+      let columns = vec![6, 0, 5, 4];
+      let mut raw    = DiophantineSystem::from_raw(rows.clone(), columns.clone()).unwrap();
+      let mut system = DiophantineSystem::from_vectors(&rows, &columns).unwrap();
+      assert_eq!(raw.spec(), system.spec());
+      assert_eq!(raw.solutions().collect::<Vec<_>>(), system.solutions().collect::<Vec<_>>());
+
+      assert_eq!(DiophantineSystem::from_raw(vec![(0, 0, 1)], vec![1]).err(), Some(DiophantineError::InvalidRow));
+      let overflow = DiophantineSystem::from_raw(vec![(1, 0, 1)], vec![u32::MAX, 1]);
+      assert_eq!(overflow.err(), Some(DiophantineError::Overflow));
+    }
+
+    #[test]
+    fn is_unique_test() {
+      // Fixing the second row leaves the first row a single choice.
+      let mut system = DiophantineSystem::from_vectors(&[(2, 1, 3), (1, 1, 5)], &[3, 4]).unwrap();
+      system.fix_cell(1, 0, 1).unwrap();
+      system.fix_cell(1, 1, 2).unwrap();
+      assert!(system.is_unique());
+      assert!(system.solve());
+      assert!(system.is_unique());
+      assert!(!system.solve());
+
+      let mut system = DiophantineSystem::from_vectors(&[(2, 1, 3), (1, 1, 5)], &[3, 4]).unwrap();
+      assert!(!system.is_unique());
+      assert_eq!(system.count_solutions(), 5);
+      let mut system = DiophantineSystem::from_vectors(&[(3, 1, 4), (2, 1, 4)], &[6, 1, 4]).unwrap();
+      assert!(!system.is_unique());
+    }
+
+    #[test]
+    fn reconstructed_columns_test() {
+      let mut system = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 0, 5, 4]).unwrap();
+      system.set_column_sorting(true).unwrap();
+      system.for_each_solution(|system| {
+        assert_eq!(system.reconstructed_columns(), vec![6, 0, 5, 4]);
+        true
+      });
+
+      let mut system = DiophantineSystem::from_vectors(&[(2, 1, 3), (1, 1, 5)], &[3, 4]).unwrap();
+      system.set_row_merging(true).unwrap();
+      assert!(system.solve());
+      assert_eq!(system.reconstructed_columns(), vec![3, 4]);
+    }
+
+    #[test]
+    fn last_row_shortcut_test() {
+      type Case = (&'static [(u32, u32, u32)], &'static [u32]);
+      let cases: [Case; 3] = [
+        (&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]),
+        (&[(2, 1, 3), (1, 1, 5)], &[3, 4]),
+        (&[(2, 0, 9), (3, 0, 9)], &[7, 6, 0]),
+      ];
+      for &(rows, columns) in cases.iter() {
+        let mut system  = DiophantineSystem::from_vectors(rows, columns).unwrap();
+        let mut general = system.clone();
+        general.disable_last_row_shortcut().unwrap();
+        let expected: Vec<Solution<u32>> = system.solutions().collect();
+        assert_eq!(general.solutions().collect::<Vec<_>>(), expected);
+        assert!(general.is_exhausted());
+        assert_eq!(general.disable_last_row_shortcut(), Err(DiophantineError::SystemClosed));
+      }
+    }
+
+    #[test]
+    fn from_matrix_test() {
+      let a = vec![vec![3, 2, 1]; 3];
+      let mut system = DiophantineSystem::from_matrix(&a, &[6, 5, 4], &[(0, 4), (0, 4), (1, 3)]).unwrap();
+      let mut expected = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]).unwrap();
+      assert_eq!(system.spec(), expected.spec());
+      assert_eq!(system.count_solutions(), expected.count_solutions());
+
+      // A single equation 2x + y == 7 with 1 <= x <= 2.
+      let mut system = DiophantineSystem::from_matrix(&[vec![2, 1]], &[7], &[(1, 2), (0, UNBOUNDED)]).unwrap();
+      assert_eq!(system.count_solutions(), 2);
+
+      let invalid = |a: &[Vec<u32>], b: &[u32]| DiophantineSystem::from_matrix(a, b, &[(0, 4), (0, 4)]).err();
+      assert_eq!(invalid(&[vec![2, 1], vec![2, 1]], &[3]), Some(DiophantineError::InvalidMatrix));
+      assert_eq!(invalid(&[vec![2, 1], vec![2]], &[3, 4]), Some(DiophantineError::InvalidMatrix));
+      assert_eq!(invalid(&[vec![2, 1], vec![1, 2]], &[3, 4]), Some(DiophantineError::InvalidMatrix));
+      assert_eq!(invalid(&[vec![0, 1]], &[3]), Some(DiophantineError::InvalidRow));
+      assert_eq!(invalid(&[], &[]), None);
+    }
+
+    #[test]
+    fn solve_checked_test() {
+      let mut system = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]).unwrap();
+      let mut count = 0;
+      while system.solve_checked() {
+        count += 1;
+      }
+      assert_eq!(count, 17);
+      assert!(!system.solve_checked());
+
+      let mut system = DiophantineSystem::from_vectors(&[(1, 0, 4), (1, 0, 4), (1, 1, 9)], &[3, 0, 2, 1]).unwrap();
+      system.set_row_merging(true).unwrap();
+      let mut count = 0;
+      while system.solve_checked() {
+        count += 1;
+      }
+      assert_eq!(count, 150);
+    }
+
+    #[test]
+    fn collect_solutions_into_test() {
+      let mut system = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]).unwrap();
+      let expected: Vec<Vec<u32>> = system.clone().solutions().map(|solution| {
+        (0..3).flat_map(|r| solution.row(r).to_vec()).collect()
+      }).collect();
+
+      let mut buf = vec![vec![9; 20]; 10];
+      let mut found = Vec::new();
+      loop {
+        let written = system.collect_solutions_into(&mut buf, 5);
+        assert_eq!(written, buf.len());
+        found.extend(buf.iter().cloned());
+        if written < 5 {
+          break;
+        }
+      }
+      assert_eq!(found, expected);
+      assert_eq!(system.collect_solutions_into(&mut buf, 5), 0);
+      assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn working_columns_test() {
+      let mut system = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]).unwrap();
+      assert_eq!(system.working_columns(), &[6, 5, 4]);
+      system.for_each_solution(|system| {
+        // The row with coefficient 1 is searched last and takes what is left.
+        let last: Vec<u32> = (0..3).map(|c| system.solution_unchecked(2, c)).collect();
+        assert_eq!(system.working_columns(), &last[..]);
+        true
+      });
+
+      let mut system = DiophantineSystem::from_vectors(&[(2, 0, 4), (1, 1, 3)], &[1, 6]).unwrap();
+      system.set_column_sorting(true).unwrap();
+      assert!(system.solve());
+      assert_eq!(system.working_columns(), &[system.solution_unchecked(1, 1), system.solution_unchecked(1, 0)]);
+    }
+
+    #[test]
+    fn extend_test() {
+      let mut system = DiophantineSystem::new(3, 3);
+      system.extend(vec![(3, 0, 4), (2, 0, 4)]);
+      system.extend(Some((1, 1, 3)));
+      system.extend_columns(vec![6, 5, 4]).unwrap();
+      let expected = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]).unwrap();
+      assert_eq!(system.spec(), expected.spec());
+
+      assert_eq!(system.extend_columns(vec![1, u32::MAX, 2]), Err(DiophantineError::Overflow));
+      assert_eq!(system.column_count(), 4);
+    }
+
+    #[test]
+    fn solution_delta_test() {
+      let mut system = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]).unwrap();
+      system.set_solution_delta(true).unwrap();
+      let mut previous = vec![0u32; 9];
+      let mut count    = 0;
+      while system.solve() {
+        let mut current = previous.clone();
+        for (r, c, change) in system.solution_delta() {
+          assert_ne!(change, 0);
+          current[3 * r + c] = (current[3 * r + c] as i64 + change) as u32;
+        }
+        assert_eq!(current, system.solution_flat());
+        previous = current;
+        count += 1;
+      }
+      assert_eq!(count, 17);
+
+      system.reset();
+      assert!(system.solve());
+      let nonzero: Vec<(usize, usize, i64)> =
+        system.solution_nonzero().iter().map(|&(r, c, value)| (r, c, value as i64)).collect();
+      assert_eq!(system.solution_delta(), nonzero);
+
+      let mut system = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]).unwrap();
+      assert!(system.solve());
+      assert_eq!(system.set_solution_delta(true), Err(DiophantineError::SystemClosed));
+      assert!(std::panic::catch_unwind(|| system.solution_delta()).is_err());
+
+      // An entry beyond `i64::MAX` is a change that does not fit.
+      let mut system: DiophantineSystem<u64> = DiophantineSystem::with_capacity(1, 1);
+      system.insert_row(1, 0, u64::UNBOUNDED).unwrap();
+      system.insert_column(u64::MAX).unwrap();
+      system.set_solution_delta(true).unwrap();
+      assert!(system.solve());
+      assert!(std::panic::catch_unwind(|| system.solution_delta()).is_err());
+    }
+
+    #[test]
+    fn deep_backtracking_test() {
+      // With the `debug_checks` feature, the residual columns are checked after every row of the search. The counts
+      // are from a brute-force enumeration.
+      type Case = (&'static [(u32, u32, u32)], &'static [u32], u64);
+      let cases: [Case; 2] = [
+        (&[(3, 0, 3), (3, 1, 4), (2, 0, 4), (2, 1, 5), (1, 0, 9)], &[7, 5, 9, 4], 49997),
+        (&[(4, 1, 2), (3, 0, 3), (3, 1, 3), (2, 1, 2), (2, 0, 3)], &[9, 8, 10], 371),
+      ];
+      for &(rows, columns, expected) in cases.iter() {
+        for &shortcut in [true, false].iter() {
+          let mut system = DiophantineSystem::from_vectors(rows, columns).unwrap();
+          if !shortcut {
+            system.disable_last_row_shortcut().unwrap();
+          }
+          let mut count = 0;
+          while system.try_solve() == Ok(true) {
+            count += 1;
+          }
+          assert_eq!(system.try_solve(), Ok(false));
+          assert_eq!(count, expected);
+          assert!(system.stats().backtracks > 100);
+        }
+      }
+    }
+
+    #[test]
+    fn induced_coefficients_test() {
+      let mut system = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]).unwrap();
+      let mut determined = 0;
+      system.for_each_solution(|system| {
+        if let Some(coeffs) = induced_coefficients(&system.solution_matrix(), &[6, 5, 4]) {
+          assert_eq!(coeffs, vec![3, 2, 1]);
+          determined += 1;
+        }
+        true
+      });
+      assert!(determined > 0);
+
+      // Dependent rows, no solution, and a solution that is not a positive integer.
+      assert_eq!(induced_coefficients(&[vec![1, 1], vec![2, 2]], &[3, 3]), None);
+      assert_eq!(induced_coefficients(&[vec![1, 0], vec![0, 1], vec![1, 1]], &[3, 3]), None);
+      assert_eq!(induced_coefficients(&[vec![2, 0], vec![0, 1]], &[3, 1]), None);
+      assert_eq!(induced_coefficients(&[vec![1, 1], vec![1, 2]], &[3, 2]), None);
+      assert_eq!(induced_coefficients(&[vec![1, 1], vec![1, 2]], &[5, 8]), Some(vec![2, 3]));
+      assert_eq!(induced_coefficients(&[vec![1, 1, 0], vec![0, 1, 1]], &[2, 5, 3]), Some(vec![2, 3]));
+      assert_eq!(induced_coefficients(&[vec![1, 1, 0], vec![0, 1, 1]], &[2, 5, 4]), None);
+    }
+
+    #[test]
+    fn system_fingerprint_test() {
+      let mut system = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]).unwrap();
+      system.set_cell_max(0, 1, 1).unwrap();
+      system.fix_cell(2, 2, 4).unwrap();
+      let fingerprint = system.system_fingerprint();
+
+      // The same system with its rows and columns inserted in a different order.
+      let mut permuted = DiophantineSystem::from_vectors(&[(1, 1, 3), (3, 0, 4), (2, 0, 4)], &[4, 6, 5]).unwrap();
+      permuted.set_cell_max(1, 2, 1).unwrap();
+      permuted.fix_cell(0, 0, 4).unwrap();
+      assert_eq!(permuted.system_fingerprint(), fingerprint);
+      while system.solve() {}
+      assert_eq!(system.system_fingerprint(), fingerprint);
+
+      // A bound on a different entry, a different row, or a different column.
+      let mut moved = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]).unwrap();
+      moved.set_cell_max(1, 1, 1).unwrap();
+      moved.fix_cell(2, 2, 4).unwrap();
+      assert_ne!(moved.system_fingerprint(), fingerprint);
+      let plain = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]).unwrap();
+      let other = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 2)], &[6, 5, 4]).unwrap();
+      assert_ne!(plain.system_fingerprint(), other.system_fingerprint());
+      let other = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 3]).unwrap();
+      assert_ne!(plain.system_fingerprint(), other.system_fingerprint());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn solve_into_channel_test() {
+      let system       = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]).unwrap();
+      let mut copy     = system.clone();
+      let mut expected = Vec::new();
+      while copy.solve() {
+        expected.push(copy.solution_matrix());
+      }
+
+      let (tx, rx) = std::sync::mpsc::channel();
+      let producer = std::thread::spawn(move || system.solve_into_channel(tx));
+      let received: Vec<Vec<Vec<u32>>> = rx.iter().collect();
+      producer.join().unwrap();
+      assert_eq!(received, expected);
+
+      // A consumer that hangs up early stops the producer.
+      let system   = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]).unwrap();
+      let (tx, rx) = std::sync::mpsc::channel();
+      drop(rx);
+      system.solve_into_channel(tx);
+    }
+
+    #[test]
+    fn duplicate_row_order_test() {
+      // Rows alike in coefficient and bounds are searched in insertion order, whatever the row order.
+      let rows = [(2, 0, 3), (3, 0, 4), (2, 0, 3), (3, 0, 4), (2, 0, 3)];
+      for &order in [RowOrder::DescCoeff, RowOrder::DescMaxProduct].iter() {
+        let mut system = DiophantineSystem::from_vectors(&rows, &[6, 5, 4]).unwrap();
+        system.set_row_order(order).unwrap();
+        let mut solutions = Vec::new();
+        while system.solve() {
+          solutions.push(system.solution_flat());
+        }
+        assert_eq!(system.row_permutation(), &[2, 0, 3, 1, 4]);
+
+        let mut again = DiophantineSystem::from_vectors(&rows, &[6, 5, 4]).unwrap();
+        again.set_row_order(order).unwrap();
+        for flat in solutions.iter() {
+          assert!(again.solve());
+          assert_eq!(&again.solution_flat(), flat);
+        }
+        assert!(!again.solve());
+      }
+    }
+
+    #[test]
+    fn solve_with_zero_rows_test() {
+      let rows = [(3, 0, 4), (2, 0, 4), (1, 0, UNBOUNDED), (1, 0, 3)];
+      let mut system = DiophantineSystem::from_vectors(&rows, &[6, 5, 4]).unwrap();
+      let mut zeroed = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 0), (1, 0, 0), (1, 0, 3)], &[6, 5, 4])
+          .unwrap();
+      let mut count = 0;
+      while system.solve_with_zero_rows(&[1, 2]).unwrap() {
+        assert!(zeroed.solve());
+        assert_eq!(system.solution_matrix(), zeroed.solution_matrix());
+        assert!(system.solution_row(1).iter().chain(system.solution_row(2).iter()).all(|&entry| entry == 0));
+        count += 1;
+      }
+      assert!(!zeroed.solve());
+      assert!(count > 0);
+
+      // The constraint survives `reset()`, and the rows keep their bounds as inserted.
+      system.reset();
+      assert_eq!(system.count_solutions(), count);
+      assert_eq!(system.row_max_size(1), 4);
+      assert_eq!(system.row_max_size(2), UNBOUNDED);
+      assert_eq!(system.spec().rows[2].max_size, UNBOUNDED);
+
+      // A row that must be nonempty cannot be zero, unless it is the extension row.
+      let mut system = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 1, 4), (1, 0, 6)], &[6, 5, 4]).unwrap();
+      assert_eq!(system.solve_with_zero_rows(&[1]), Ok(false));
+      assert!(!system.solve());
+      let mut system = DiophantineSystem::from_vectors(&[(3, 1, 4), (2, 1, 4), (1, 1, 6)], &[6, 5, 4]).unwrap();
+      system.set_extension_row(1).unwrap();
+      assert_eq!(system.solve_with_zero_rows(&[1]), Ok(true));
+      assert_eq!(system.solution_row(1), vec![0, 0, 0]);
+
+      // A row that does not exist is reported before any row is constrained.
+      let mut system = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 0, 6)], &[6, 5, 4]).unwrap();
+      assert_eq!(
+        system.solve_with_zero_rows(&[1, 3]),
+        Err(DiophantineError::IndexOutOfRange { row: 3, col: 0, row_count: 3, col_count: 3 })
+      );
+      let fresh = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 0, 6)], &[6, 5, 4]).unwrap();
+      assert_eq!(system.count_solutions(), fresh.clone().count_solutions());
+
+      // A zero row is not interchangeable with an otherwise identical row, and is carried along by `spec()`.
+      let mut system = DiophantineSystem::from_vectors(&[(1, 0, 5), (1, 0, 5)], &[2, 1]).unwrap();
+      let plain      = system.clone();
+      assert_eq!(system.solve_with_zero_rows(&[1]), Ok(true));
+      let expected = vec![vec![vec![2, 1], vec![0, 0]]];
+      assert_eq!(vec![system.solution_matrix()], expected);
+      let spec = system.spec();
+      assert_eq!(spec.zero_rows, vec![1]);
+      assert_eq!(spec.to_system().unwrap().count_solutions_canonical(), 1);
+      assert_eq!(spec.to_system().unwrap().solve_deduplicated().collect::<Vec<_>>(), expected);
+      assert_eq!(system.brute_force_solutions(), expected);
+      assert_ne!(system.system_fingerprint(), plain.system_fingerprint());
+    }
+
+    #[test]
+    fn precompute_now_test() {
+      let mut system = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]).unwrap();
+      assert_eq!(system.precompute_now(), Ok(()));
+      assert_eq!(system.precompute_now(), Ok(()));
+      assert_eq!(system.insert_column(1), Err(DiophantineError::SystemClosed));
+      assert_eq!(system.count_solutions(), 17);
+      assert_eq!(system.precompute_now(), Ok(()));
+
+      // Insoluble by the row bounds, by a column no row combination makes up, and with no room for the solubility
+      // vectors.
+      let mut system = DiophantineSystem::from_vectors(&[(3, 2, 4), (2, 2, 4)], &[6, 1]).unwrap();
+      assert_eq!(system.precompute_now(), Err(DiophantineError::Infeasible));
+      assert_eq!(system.precompute_now(), Err(DiophantineError::Infeasible));
+      assert!(!system.solve());
+      let mut system = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4)], &[6, 1]).unwrap();
+      assert_eq!(system.precompute_now(), Err(DiophantineError::Infeasible));
+      let mut system = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4)], &[6, 5]).unwrap();
+      system.set_max_solubility_memory(Some(0)).unwrap();
+      assert_eq!(system.precompute_now(), Err(DiophantineError::TooLarge));
+    }
+
+    #[test]
+    fn solubility_storage_test() {
+      // Two rows with solubility vectors for column values 0 to 6 take 14 entries, whose size depends on the layout.
+      let entry  = if cfg!(feature = "compact_solubility") { 8 } else { 16 };
+      let system = |bytes| {
+        let mut system = DiophantineSystem::<u64>::with_capacity(2, 2);
+        system.insert_row(3, 0, 4).unwrap();
+        system.insert_row(2, 0, 4).unwrap();
+        system.insert_column(6).unwrap();
+        system.insert_column(5).unwrap();
+        system.set_max_solubility_memory(Some(bytes)).unwrap();
+        system
+      };
+      let mut fits = system(14 * entry);
+      assert_eq!(fits.try_solve(), Ok(true));
+      assert_eq!(fits.solubility(0).unwrap().len(), 7);
+      let mut too_large = system(14 * entry - 1);
+      assert_eq!(too_large.try_solve(), Err(DiophantineError::TooLarge));
+    }
+
+    #[test]
+    fn sample_solution_test() {
+      let mut system = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]).unwrap();
+      let mut solutions = Vec::new();
+      while system.solve() {
+        solutions.push(system.solution_matrix());
+      }
+
+      // A linear congruential generator stands in for a random number generator.
+      let mut state: u64 = 12345;
+      let mut random = |n: usize| {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        ((state >> 33) as usize) % n
+      };
+      let mut seen = Vec::new();
+      for _ in 0..100 {
+        let sample = system.sample_solution(&mut random).unwrap();
+        assert!(solutions.contains(&sample));
+        if !seen.contains(&sample) {
+          seen.push(sample);
+        }
+      }
+      assert!(seen.len() > 1);
+
+      // Fixed entries are kept, and a system without solutions has no sample.
+      let mut fixed = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]).unwrap();
+      fixed.fix_cell(0, 0, 2).unwrap();
+      assert_eq!(fixed.sample_solution(&mut random).unwrap()[0][0], 2);
+      let insoluble = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4)], &[6, 1]).unwrap();
+      assert_eq!(insoluble.sample_solution(&mut random), None);
+
+      // An entry with a huge range of values. Always drawing the last remaining candidate tries the greatest value of
+      // each entry first and then zero, which the shuffle has moved into its place.
+      let mut huge: DiophantineSystem<u64> = DiophantineSystem::with_capacity(2, 1);
+      huge.insert_row(1, 0, u64::UNBOUNDED).unwrap();
+      huge.insert_row(1, 0, u64::UNBOUNDED).unwrap();
+      huge.insert_column(u64::MAX / 4).unwrap();
+      assert_eq!(huge.sample_solution(|n| n - 1), Some(vec![vec![u64::MAX / 4], vec![0]]));
+    }
+
+    #[test]
+    fn shrink_to_fit_test() {
+      for &merging in [false, true].iter() {
+        let rows = [(3, 0, 4), (2, 0, 4), (2, 0, 4), (1, 1, 3)];
+        let mut system = DiophantineSystem::from_vectors(&rows, &[6, 5, 4]).unwrap();
+        system.set_row_merging(merging).unwrap();
+        let mut expected = Vec::new();
+        while system.solve() {
+          expected.push(system.solution_matrix());
+        }
+        system.reset();
+
+        // Shrinking mid-search leaves the search where it was, and the solubility vectors in place.
+        assert!(system.solve());
+        assert_eq!(system.solution_matrix(), expected[0]);
+        let solubility = system.solubility(0);
+        system.shrink_to_fit();
+        assert_eq!(system.solubility(0), solubility);
+        assert_eq!(system.solution_matrix(), expected[0]);
+        for matrix in expected[1..].iter() {
+          assert!(system.solve());
+          assert_eq!(&system.solution_matrix(), matrix);
+        }
+        assert!(!system.solve());
+        system.shrink_to_fit();
+        system.reset();
+        assert_eq!(system.count_solutions(), expected.len() as u64);
+      }
+    }
+
+    #[cfg(feature = "latex")]
+    #[test]
+    fn latex_test() {
+      let mut system = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, UNBOUNDED)], &[6, 5, 4]).unwrap();
+      let expected = concat!(
+        "\\begin{pmatrix}\n3 & 2 & 1\n\\end{pmatrix}\n",
+        "\\begin{pmatrix}\n",
+        "M_{0,0} & M_{0,1} & M_{0,2} \\\\\nM_{1,0} & M_{1,1} & M_{1,2} \\\\\nM_{2,0} & M_{2,1} & M_{2,2}\n",
+        "\\end{pmatrix}\n=\n\\begin{pmatrix}\n6 & 5 & 4\n\\end{pmatrix}\n",
+      );
+      assert_eq!(system.system_latex(), expected);
+      while system.solve() {
+        if system.solution_matrix() == vec![vec![2, 0, 0], vec![0, 1, 0], vec![0, 3, 4]] {
+          break;
+        }
+      }
+      let expected = "\\begin{pmatrix}\n2 & 0 & 0 \\\\\n0 & 1 & 0 \\\\\n0 & 3 & 4\n\\end{pmatrix}\n";
+      assert_eq!(system.solution_latex(), expected);
+    }
+
+    #[test]
+    fn solve_by_total_ascending_test() {
+      let cases = [
+        (vec![(3, 0, 4), (2, 0, 4), (1, 1, 3)], vec![6, 5, 4]),
+        (vec![(2, 0, 9), (3, 0, 9)], vec![7, 6, 0]),
+        (vec![(4, 1, 2), (3, 0, 3), (3, 1, 3), (2, 1, 2), (2, 0, 3)], vec![9, 8, 10]),
+        (vec![(2, 1, 3), (1, 2, 5)], vec![1]),
+      ];
+      for (rows, columns) in cases.iter() {
+        let mut system = DiophantineSystem::from_vectors(rows, columns).unwrap();
+        let mut expected = Vec::new();
+        while system.solve() {
+          expected.push(system.solution_flat());
+        }
+        expected.sort_by_key(|flat| flat.iter().sum::<u32>());
+
+        let mut system = DiophantineSystem::from_vectors(rows, columns).unwrap();
+        let mut found = Vec::new();
+        while system.solve_by_total_ascending() {
+          found.push(system.solution_flat());
+        }
+        assert!(!system.solve_by_total_ascending());
+        assert_eq!(found, expected);
+      }
+    }
+
+    #[test]
+    fn forced_total_test() {
+      let mut system = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]).unwrap();
+      let mut forced_somewhere = false;
+      while system.solve() {
+        for i in 0..3 {
+          let sum: u32 = system.solution_row(i).iter().sum();
+          let forced: u32 = (0..3).map(|c| system.solution_base(i, c).unwrap()).sum();
+          assert_eq!(system.forced_total(i), forced);
+          assert_eq!(system.forced_total(i) + system.free_total(i), sum);
+          forced_somewhere |= i < 2 && forced > 0;
+        }
+        // The last row in search order takes what is left, all of it forced.
+        assert_eq!(system.free_total(2), 0);
+      }
+      assert!(forced_somewhere);
+    }
+
+    #[test]
+    fn cell_count_max_test() {
+      let cases = [
+        (vec![(3, 0, 4), (2, 0, 4), (1, 1, 3)], vec![6, 5, 4]),
+        (vec![(2, 0, 9), (3, 0, 9)], vec![7, 6, 0]),
+        (vec![(4, 1, 2), (3, 0, 3), (3, 1, 3), (2, 1, 2), (2, 0, 3)], vec![9, 8, 10]),
+      ];
+      for (rows, columns) in cases.iter() {
+        let mut system = DiophantineSystem::from_vectors(rows, columns).unwrap();
+        let mut all = Vec::new();
+        while system.solve() {
+          all.push(system.solution_matrix());
+        }
+
+        for i in 0..rows.len() {
+          for &lex in [false, true].iter() {
+            let nonzero = |matrix: &Vec<Vec<u32>>| matrix[i].iter().filter(|&&entry| entry > 0).count();
+            let mut expected: Vec<_> = all.iter().filter(|matrix| nonzero(matrix) <= 1).cloned().collect();
+            let mut system = DiophantineSystem::from_vectors(rows, columns).unwrap();
+            system.set_row_cell_count_max(i, 1).unwrap();
+            let mut found = Vec::new();
+            if lex {
+              while system.solve_lex() {
+                found.push(system.solution_matrix());
+              }
+              expected.sort_by_key(|matrix| matrix.concat());
+            } else {
+              while system.solve() {
+                found.push(system.solution_matrix());
+              }
+            }
+            assert_eq!(found, expected);
+          }
+        }
+      }
+
+      // The limit is part of the system's description.
+      let mut system = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]).unwrap();
+      assert_eq!(
+        system.set_row_cell_count_max(3, 1),
+        Err(DiophantineError::IndexOutOfRange { row: 3, col: 0, row_count: 3, col_count: 3 })
+      );
+      system.set_row_cell_count_max(2, 1).unwrap();
+      assert_eq!(system.spec().cell_count_max, vec![(2, 1)]);
+      assert_eq!(system.spec().to_system().unwrap().count_solutions(), system.count_solutions());
+      assert_eq!(system.set_row_cell_count_max(0, 1), Err(DiophantineError::SystemClosed));
+    }
+
+    #[test]
+    fn solve_batch_test() {
+      let rows        = [(3, 0, 4), (2, 0, 4), (1, 1, 3)];
+      let column_sets = vec![vec![4, 5, 6], vec![6, 5, 4], vec![9, 9], vec![1, 1, 1, 1], vec![2, 2], vec![7, 3, 4]];
+      let solutions   = DiophantineSystem::solve_batch(&rows, &column_sets).unwrap();
+      assert_eq!(solutions.len(), column_sets.len());
+      for (columns, solution) in column_sets.iter().zip(solutions.iter()) {
+        let expected = DiophantineSystem::from_vectors(&rows, columns).unwrap().first_solution();
+        assert_eq!(solution, &expected, "columns {:?}", columns);
+      }
+      assert!(solutions[3].is_none());
+      assert!(DiophantineSystem::solve_batch(&[(2, 3, 1)], &column_sets).is_err());
+    }
+
+    #[test]
+    fn row_leave_bounds_test() {
+      let mut system = DiophantineSystem::from_vectors(&[(1, 1, 3), (3, 0, 4), (2, 0, 4)], &[6, 5, 4]).unwrap();
+      assert_eq!(system.row_leave_bounds(0), None);
+      system.classify();
+      // Search order is (3, 0, 4), (2, 0, 4), (1, 1, 3).
+      assert_eq!(system.row_leave_bounds(0), Some((1, 11)));
+      assert_eq!(system.row_leave_bounds(1), Some((1, 3)));
+      assert_eq!(system.row_leave_bounds(2), Some((0, 0)));
+      assert_eq!(system.row_leave_bounds(3), None);
+    }
+
+    #[test]
+    fn allocation_failed_test() {
+      // Solubility vectors for column values up to 2^60 cannot be allocated, or with the compact layout stored at all.
+      let mut system = DiophantineSystem::<u64>::with_capacity(2, 1);
+      system.insert_row(3, 0, u64::UNBOUNDED).unwrap();
+      system.insert_row(2, 0, u64::UNBOUNDED).unwrap();
+      system.insert_column(1 << 60).unwrap();
+      let expected = if cfg!(feature = "compact_solubility") {
+        DiophantineError::TooLarge
+      } else {
+        DiophantineError::AllocationFailed
+      };
+      assert_eq!(system.try_solve(), Err(expected));
+      assert_eq!(system.try_solve(), Err(expected));
+      system.reset();
+      assert!(!system.solve());
+    }
+
+    #[test]
+    fn solve_deduplicated_test() {
+      // Rows 0 and 2 are interchangeable: 37 solutions in 19 classes.
+      let mut system = DiophantineSystem::from_vectors(&[(2, 0, 3), (1, 1, 4), (2, 0, 3)], &[4, 3, 5]).unwrap();
+      let solutions: Vec<Vec<Vec<u32>>> = system.solve_deduplicated().collect();
+      assert_eq!(solutions.len(), 19);
+      for (k, a) in solutions.iter().enumerate() {
+        for b in solutions[..k].iter() {
+          let swapped = vec![b[2].clone(), b[1].clone(), b[0].clone()];
+          assert!(a != b && *a != swapped);
+        }
+      }
+      assert_eq!(system.solve_deduplicated().count(), 0);
+
+      // Three interchangeable rows.
+      let rows = [(1, 0, 3), (1, 0, 3), (1, 0, 3), (2, 1, 2)];
+      let mut system = DiophantineSystem::from_vectors(&rows, &[3, 2, 4]).unwrap();
+      assert_eq!(system.solve_deduplicated().count(), 89);
+    }
+
+    #[test]
+    fn explain_infeasibility_test() {
+      let explain = |rows: &[(u32, u32, u32)], columns: &[u32]| {
+        let mut system = DiophantineSystem::from_vectors(rows, columns).unwrap();
+        assert_eq!(system.explain_infeasibility(), None);
+        system.solve();
+        system.explain_infeasibility()
+      };
+      assert_eq!(explain(&[(3, 0, 4), (2, 0, 4), (1, 1, 3)], &[6, 5, 4]), None);
+      assert_eq!(
+        explain(&[(3, 2, 4), (2, 2, 4)], &[4, 5]),
+        Some(Infeasibility::MinProductsTooLarge { sum_of_min: 10, column_sum: 9 })
+      );
+      assert_eq!(
+        explain(&[(3, 0, 1), (1, 0, 2)], &[4, 5]),
+        Some(Infeasibility::MaxProductsTooSmall { sum_of_max: 5, column_sum: 9 })
+      );
+      assert_eq!(
+        explain(&[(4, 0, 5), (2, 0, 5)], &[8, 6, 7, 2]),
+        Some(Infeasibility::InsolubleColumn { column: 2, value: 7 })
+      );
+      assert_eq!(explain(&[], &[1]), Some(Infeasibility::MaxProductsTooSmall { sum_of_max: 0, column_sum: 1 }));
+
+      let mut system = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4)], &[6, 5]).unwrap();
+      system.fix_cell(0, 1, 2).unwrap();
+      assert!(!system.solve());
+      assert_eq!(system.explain_infeasibility(), Some(Infeasibility::InconsistentConstraints));
+      system.reset();
+      assert_eq!(system.explain_infeasibility(), None);
+
+      // With row merging, the columns are reported in insertion order.
+      let mut system = DiophantineSystem::from_vectors(&[(4, 0, 5), (4, 0, 5), (2, 0, 5)], &[6, 8, 7]).unwrap();
+      system.set_row_merging(true).unwrap();
+      system.set_column_sorting(true).unwrap();
+      assert!(!system.solve());
+      assert_eq!(system.explain_infeasibility(), Some(Infeasibility::InsolubleColumn { column: 2, value: 7 }));
+    }
+
+    #[test]
+    fn solution_sorted_test() {
+      let mut system = DiophantineSystem::from_vectors(&[(1, 1, 3), (3, 0, 4), (2, 0, 4)], &[6, 5, 4]).unwrap();
+      while system.solve() {
+        for i in 0..3 {
+          let sorted_row = system.row_permutation()[i] as usize;
+          for c in 0..3 {
+            assert_eq!(system.solution_sorted(sorted_row, c), system.solution(i, c).unwrap());
+          }
+        }
+        // Search order puts the row with coefficient 3 first.
+        assert_eq!(system.solution_sorted(0, 0), system.solution(1, 0).unwrap());
+      }
+    }
+
+    #[test]
+    fn solubility_tables_test() {
+      let rows = [(4, 1, 2), (3, 0, 3), (3, 1, 3), (2, 1, 2), (2, 0, 3)];
+      let mut source = DiophantineSystem::from_vectors(&rows, &[9, 8, 10]).unwrap();
+      assert!(source.extract_solubility().is_none());
+      source.classify();
+      let tables = source.extract_solubility().unwrap();
+      assert_eq!(tables.max_column_value(), 10);
+      assert_eq!(tables.row_count(), 5);
+
+      let count = |mut system: DiophantineSystem| system.count_solutions();
+      for columns in [[9, 8, 10], [7, 10, 6], [12, 3, 5]].iter() {
+        let mut system = DiophantineSystem::with_solubility(&rows, columns, &tables).unwrap();
+        system.classify();
+        if columns.iter().all(|&column| column <= 10) {
+          // The tables are used, cut down to the largest column.
+          let length = *columns.iter().max().unwrap() as usize + 1;
+          for i in 0..rows.len() {
+            assert_eq!(system.solubility(i).unwrap(), source.solubility(i).unwrap()[..length].to_vec());
+          }
+        }
+        assert_eq!(count(system), count(DiophantineSystem::from_vectors(&rows, columns).unwrap()));
+      }
+      let system = DiophantineSystem::with_solubility(&rows, &[9, 8, 10], &tables).unwrap();
+      assert_eq!(count(system), 371);
+
+      // Tables for other rows are ignored.
+      let other = [(4, 1, 2), (3, 0, 3), (3, 1, 3), (2, 1, 2), (5, 0, 3)];
+      let system = DiophantineSystem::with_solubility(&other, &[9, 8, 10], &tables).unwrap();
+      assert_eq!(count(system), count(DiophantineSystem::from_vectors(&other, &[9, 8, 10]).unwrap()));
+    }
+
+    #[test]
+    fn brute_force_test() {
+      // Small random systems, simple and complex, some with an extension row or bounds on entries.
+      let mut state: u64 = 2024;
+      let mut random = |n: u32| {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (state >> 33) as u32 % n
+      };
+      for _ in 0..300 {
+        let mut system = DiophantineSystem::new(4, 3);
+        for _ in 0..1 + random(4) {
+          let coeff    = 1 + random(4);
+          let min_size = random(3);
+          let max_size = if random(4) == 0 { UNBOUNDED } else { min_size + random(4) };
+          system.insert_row(coeff, min_size, max_size).unwrap();
+        }
+        for _ in 0..1 + random(3) {
+          system.insert_column(random(9)).unwrap();
+        }
+        if random(4) == 0 {
+          system.set_extension_row(random(system.row_count() as u32) as usize).unwrap();
+        }
+        if random(4) == 0 {
+          let (r, c) = (random(system.row_count() as u32) as usize, random(system.column_count() as u32) as usize);
+          system.set_cell_max(r, c, random(3)).unwrap();
+        }
+
+        let expected   = system.brute_force_solutions();
+        let mut found  = Vec::new();
+        while system.solve() {
+          found.push(system.solution_matrix());
+        }
+        found.sort();
+        assert_eq!(found, expected, "{:?}", system.spec());
+      }
+    }
+
+    #[test]
+    fn is_representable_test() {
+      // Complex: the rows make up only even values, and the row with coefficient 2 alone only those up to 6.
+      let mut system = DiophantineSystem::from_vectors(&[(2, 0, 3), (4, 0, 5)], &[8, 6, 4, 2]).unwrap();
+      assert_eq!(system.classify(), SystemClass::Complex);
+      for value in 0..=8 {
+        assert_eq!(system.is_representable(value, 0), value % 2 == 0);
+        assert_eq!(system.is_representable(value, 1), value % 2 == 0 && value <= 6);
+        assert_eq!(system.is_representable(value, 2), value == 0);
+      }
+
+      // Simple: the last row makes up anything.
+      let mut system = DiophantineSystem::from_vectors(&[(3, 0, 4), (2, 0, 4), (1, 1, 6)], &[6, 5, 4]).unwrap();
+      assert_eq!(system.classify(), SystemClass::Simple);
+      assert!((0..=6).all(|value| (0..3).all(|i| system.is_representable(value, i))));
+    }
+
+    #[test]
+    fn empty_row_test() {
+      // Rows with a `max_size` of zero only add rows of zeros, wherever they are searched.
+      let rows       = [(4, 1, 2), (3, 0, 3), (3, 1, 3), (2, 1, 2), (2, 0, 3)];
+      let empty      = [(5, 0, 0), (3, 0, 0), (1, 0, 0), (2, 0, 0)];
+      let padded: Vec<(u32, u32, u32)> = rows.iter().chain(empty.iter()).cloned().collect();
+      let mut system = DiophantineSystem::from_vectors(&padded, &[9, 8, 10]).unwrap();
+      let mut count  = 0;
+      while system.solve() {
+        assert!((rows.len()..padded.len()).all(|i| system.solution_row(i).iter().all(|&entry| entry == 0)));
+        count += 1;
+      }
+      assert_eq!(count, 371);
+      let mut plain = DiophantineSystem::from_vectors(&rows, &[9, 8, 10]).unwrap();
+      assert_eq!(plain.count_solutions(), 371);
+      assert!(system.stats().empty_rows > plain.stats().empty_rows);
+
+      // A row whose coefficient exceeds every column can only take nothing, whatever its `max_size`.
+      let wide: Vec<(u32, u32, u32)> = rows.iter().cloned().chain(Some((20, 0, 5))).collect();
+      let mut system = DiophantineSystem::from_vectors(&wide, &[9, 8, 10]).unwrap();
+      let mut plain  = DiophantineSystem::from_vectors(&rows, &[9, 8, 10]).unwrap();
+      assert_eq!(system.count_solutions(), 371);
+      assert_eq!(plain.count_solutions(), 371);
+      assert!(system.stats().empty_rows > plain.stats().empty_rows);
+
+      // A row whose fixed entries use up its `max_size` is empty in the search.
+      let rows       = [(3, 0, 4), (2, 0, 3), (1, 1, 3), (2, 0, 0)];
+      let mut system = DiophantineSystem::from_vectors(&rows, &[6, 5, 4]).unwrap();
+      system.fix_cell(1, 0, 1).unwrap();
+      system.fix_cell(1, 1, 2).unwrap();
+      let expected  = system.brute_force_solutions();
+      let mut found = Vec::new();
+      while system.solve() {
+        found.push(system.solution_matrix());
+      }
+      assert!(!expected.is_empty());
+      found.sort();
+      assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn zero_column_test() {
+      // A complex system and a simple system, each with a zero column.
+      let cases = [
+        ([(3, 0, 4), (2, 0, 4), (1, 1, 3)], [6, 0, 5, 4], 17),
+        ([(1, 0, 4), (1, 0, 4), (1, 1, 9)], [3, 0, 2, 1], 150),
+      ];
+      for &(rows, columns, count) in cases.iter() {
+        let mut system = DiophantineSystem::new(3, 4);
+        for &(coeff, min_size, max_size) in rows.iter() {
+          system.insert_row(coeff, min_size, max_size).unwrap();
+        }
+        for &column in columns.iter() {
+          system.insert_column(column).unwrap();
+        }
+
+        let mut found = 0;
+        system.for_each_solution(|system| {
+          assert!(system.verify_current_solution());
+          for r in 0..3 {
+            assert_eq!(system.solution_unchecked(r, 1), 0);
+          }
+          found += 1;
+          true
+        });
+        assert_eq!(found, count);
+      }
+
+      // A column sum of zero.
+      let mut system = DiophantineSystem::new(2, 1);
+      system.insert_row(2, 0, 1).unwrap();
+      system.insert_row(1, 0, 1).unwrap();
+      system.insert_column(0).unwrap();
+      assert!(system.solve());
+      assert_eq!(system.solution_matrix(), vec![vec![0], vec![0]]);
+      assert!(!system.solve());
+
+      let mut system: DiophantineSystem<i64> = DiophantineSystem::with_capacity(1, 1);
+      assert_eq!(system.insert_column(-1), Err(DiophantineError::NegativeColumn));
+    }
 }
@@ -0,0 +1,218 @@
+/*!
+
+Solving groups of identical rows together.
+
+Rows with identical `(coeff, min_size, max_size)` are interchangeable, which makes the plain search repeat the same
+work for every way of distributing a group's share of $C$ among its members. With row merging enabled, each group of
+$k$ identical rows is replaced by a single row with the same coefficient and bounds `[k * min_size, k * max_size]`,
+and the resulting reduced system is solved instead. A reduced solution assigns a vector $G$ to each group, and any
+such vector whose sum lies in `[k * min_size, k * max_size]` can be split into $k$ rows with sums in
+`[min_size, max_size]`. Expanding every reduced solution into all of its splits therefore yields exactly the solutions
+of the original system.
+
+Splitting $G$ among the members of a group is itself a system with $k$ rows of coefficient $1$ and columns $G$ (zero
+entries of $G$ dropped), which we solve with a nested `DiophantineSystem`.
+
+Solutions are produced in the order of the reduced system's solutions. The splits of one reduced solution are
+enumerated like an odometer: the last group advances fastest, and when it runs out of splits the previous group
+advances and all later groups start over.
+
+*/
+
+use alloc::{collections::BTreeMap, vec::Vec};
+
+use crate::{DiophantineInt, DiophantineSystem, Infeasibility, RowOrder, SystemClass};
+
+#[derive(Clone)]
+pub(crate) struct RowMerging<T: DiophantineInt> {
+  reduced   : DiophantineSystem<T>,         // one row per group
+  groups    : Vec<Vec<usize>>,              // insertion indices of the members of each group
+  bounds    : Vec<(T, T)>,                  // (min_size, max_size) of the members of each group
+  splits    : Vec<Option<Split<T>>>,        // splits of each group's row; `None` if it has only one
+  started   : bool,
+}
+
+/// The ways of splitting a group's row of the reduced solution among its members.
+#[derive(Clone)]
+struct Split<T: DiophantineInt> {
+  system  : DiophantineSystem<T>, // the nonzero entries of the group's row split among its members
+  columns : Vec<usize>,           // columns of the reduced system corresponding to those of `system`
+}
+
+impl<T: DiophantineInt> RowMerging<T> {
+  /// Groups the rows of a system given by its rows `(coeff, min_size, max_size)` in insertion order and its columns.
+  /// Returns `None` if no two rows are identical, in which case merging gains nothing.
+  pub(crate) fn new(rows: &[(T, T, T)], columns: &[T]) -> Option<Self> {
+    let mut groups: Vec<Vec<usize>>              = Vec::new();
+    let mut keys  : Vec<(T, T, T)>               = Vec::new();
+    let mut index : BTreeMap<(T, T, T), usize>   = BTreeMap::new();
+
+    for (i, &row) in rows.iter().enumerate() {
+      let g = *index.entry(row).or_insert_with(|| {
+        groups.push(Vec::new());
+        keys.push(row);
+        groups.len() - 1
+      });
+      groups[g].push(i);
+    }
+
+    if groups.len() == rows.len() {
+      return None;
+    }
+
+    let mut reduced = DiophantineSystem::with_capacity(groups.len(), columns.len());
+    for (group, &(coeff, min_size, max_size)) in groups.iter().zip(keys.iter()) {
+      // The rows were validated when they were inserted, but the merged bounds may overflow, in which case we don't
+      // merge.
+      let k = T::from_usize(group.len());
+      let max_size = if max_size == T::UNBOUNDED { T::UNBOUNDED } else { k.checked_mul(max_size)? };
+      reduced.insert_row(coeff, k.checked_mul(min_size)?, max_size).ok()?;
+    }
+    for &column in columns.iter() {
+      reduced.insert_column(column).unwrap();
+    }
+
+    Some(RowMerging {
+      reduced,
+      groups,
+      bounds: keys.iter().map(|&(_, min_size, max_size)| (min_size, max_size)).collect(),
+      splits: Vec::new(),
+      started: false,
+    })
+  }
+
+  /// Limits the search of the merged system, which is where nearly all of the work is done.
+  pub(crate) fn set_node_budget(&mut self, budget: Option<u64>) {
+    self.reduced.set_node_budget(budget).unwrap();
+  }
+
+  pub(crate) fn set_row_order(&mut self, order: RowOrder<T>) {
+    self.reduced.set_row_order(order).unwrap();
+  }
+
+  pub(crate) fn set_max_solubility_memory(&mut self, bytes: Option<usize>) {
+    self.reduced.set_max_solubility_memory(bytes).unwrap();
+  }
+
+  /// Releases the spare capacity of the merged system and of the systems splitting its rows.
+  pub(crate) fn shrink_to_fit(&mut self) {
+    self.reduced.shrink_to_fit();
+    for split in self.splits.iter_mut().flatten() {
+      split.system.shrink_to_fit();
+    }
+  }
+
+  /// Whether the search of the merged system ran out of its node budget.
+  pub(crate) fn interrupted(&self) -> bool {
+    self.reduced.interrupted()
+  }
+
+  /// Whether the solubility vectors of the merged system would have exceeded their memory limit.
+  pub(crate) fn too_large(&self) -> bool {
+    self.reduced.too_large()
+  }
+
+  /// Why the merged system was found insoluble before its search, if it was.
+  pub(crate) fn infeasibility(&self) -> Option<Infeasibility<T>> {
+    self.reduced.explain_infeasibility()
+  }
+
+  /// Whether the solubility vectors of the merged system could not be allocated.
+  pub(crate) fn allocation_failed(&self) -> bool {
+    self.reduced.allocation_failed()
+  }
+
+  /// Whether the merged system is complex.
+  pub(crate) fn is_complex(&mut self) -> bool {
+    self.reduced.classify() == SystemClass::Complex
+  }
+
+  /// Advances to the next solution of the original system, returning false if there are no more.
+  pub(crate) fn advance(&mut self) -> bool {
+    if self.started {
+      // Advance the odometer of splits.
+      for g in (0..self.groups.len()).rev() {
+        if let Some(split) = self.splits[g].as_mut() {
+          if split.system.solve() {
+            for h in (g + 1)..self.groups.len() {
+              self.start_split(h);
+            }
+            return true;
+          }
+        }
+      }
+    }
+    self.started = true;
+
+    if !self.reduced.solve() {
+      return false;
+    }
+    self.splits.clear();
+    for g in 0..self.groups.len() {
+      self.splits.push(None);
+      self.start_split(g);
+    }
+    true
+  }
+
+  /// Builds the splits of group `g`'s row of the current reduced solution and selects the first one.
+  fn start_split(&mut self, g: usize) {
+    self.splits[g] = None;
+    if self.groups[g].len() == 1 {
+      return;
+    }
+
+    let columns: Vec<usize> = (0..self.reduced.column_count())
+      .filter(|&c| self.reduced.solution_unchecked(g, c) > T::ZERO)
+      .collect();
+    if columns.is_empty() {
+      // Every member gets nothing, which the reduced row's bounds guarantee is allowed.
+      return;
+    }
+
+    let (min_size, max_size) = self.bounds[g];
+    let mut system = DiophantineSystem::with_capacity(self.groups[g].len(), columns.len());
+    for _ in self.groups[g].iter() {
+      system.insert_row(T::ONE, min_size, max_size).unwrap();
+    }
+    for &c in columns.iter() {
+      system.insert_column(self.reduced.solution_unchecked(g, c)).unwrap();
+    }
+    // The reduced row's sum lies within the group's combined bounds, so a split always exists.
+    let found = system.solve();
+    assert!(found, "group row cannot be split");
+
+    self.splits[g] = Some(Split { system, columns });
+  }
+
+  /// Reports every entry of the current solution to `cell` as `(row, column, value)`, with rows in insertion order.
+  pub(crate) fn write_solution(&self, mut cell: impl FnMut(usize, usize, T)) {
+    let column_count = self.reduced.column_count();
+    for (g, group) in self.groups.iter().enumerate() {
+      match (group.len(), self.splits[g].as_ref()) {
+        (1, _) => {
+          for c in 0..column_count {
+            cell(group[0], c, self.reduced.solution_unchecked(g, c));
+          }
+        }
+        (_, None) => {
+          for &r in group.iter() {
+            for c in 0..column_count {
+              cell(r, c, T::ZERO);
+            }
+          }
+        }
+        (_, Some(split)) => {
+          for (j, &r) in group.iter().enumerate() {
+            for c in 0..column_count {
+              cell(r, c, T::ZERO);
+            }
+            for (k, &c) in split.columns.iter().enumerate() {
+              cell(r, c, split.system.solution_unchecked(j, k));
+            }
+          }
+        }
+      }
+    }
+  }
+}
@@ -0,0 +1,67 @@
+/*!
+
+Counting solutions on several threads, and streaming solutions to another thread.
+
+The search mutates the system in place, so each worker builds its own copy from the system's `SystemSpec`. The
+solutions are partitioned by the entry of the row with the largest coefficient in the largest column: every solution
+gives that entry exactly one of the values `0..=column / coeff`, and fixing it with `fix_cell` splits the search into
+disjoint subtrees that the workers count independently.
+
+*/
+
+use std::{sync::mpsc::Sender, thread};
+
+use crate::{DiophantineInt, DiophantineSystem};
+
+impl<T: DiophantineInt + Send + Sync> DiophantineSystem<T> {
+  /// Counts the solutions of the system using up to one thread per available core. Unlike `count_solutions`, this
+  /// counts every solution of the system as it was built, regardless of how far the search has progressed, and leaves
+  /// the system untouched. The solution limit and node budget are not applied. The count equals that of
+  /// `count_solutions` on a fresh copy of the system.
+  pub fn par_count_solutions(&self) -> u64 {
+    let spec = self.spec();
+    if spec.rows.is_empty() || spec.columns.is_empty() {
+      return spec.to_system().unwrap().count_solutions();
+    }
+
+    // The cell to split on.
+    let row = (0..spec.rows.len()).max_by_key(|&r| spec.rows[r].coeff).unwrap();
+    let col = (0..spec.columns.len()).max_by_key(|&c| spec.columns[c]).unwrap();
+    if spec.fixed.iter().any(|&(r, c, _)| r == row && c == col) {
+      return spec.to_system().unwrap().count_solutions();
+    }
+    let values = (spec.columns[col] / spec.rows[row].coeff).to_usize() + 1;
+
+    let workers = thread::available_parallelism().map_or(1, |n| n.get()).min(values);
+    let spec    = &spec;
+    thread::scope(|scope| {
+      let handles: Vec<_> = (0..workers)
+        .map(|worker| {
+          scope.spawn(move || {
+            let mut count = 0;
+            for value in (worker..values).step_by(workers) {
+              let mut system = spec.to_system().unwrap();
+              system.fix_cell(row, col, T::from_usize(value)).unwrap();
+              count += system.count_solutions();
+            }
+            count
+          })
+        })
+        .collect();
+      handles.into_iter().map(|handle| handle.join().unwrap()).sum()
+    })
+  }
+}
+
+impl<T: DiophantineInt> DiophantineSystem<T> {
+  /// Enumerates the remaining solutions, sending each as a `solution_matrix()` over `tx`, so that a consumer on
+  /// another thread can process solutions while this one searches. Returns when the solutions are exhausted, or early
+  /// if the receiving end of the channel has hung up.
+  pub fn solve_into_channel(mut self, tx: Sender<Vec<Vec<T>>>) {
+    while self.solve() {
+      if tx.send(self.solution_matrix()).is_err() {
+        return;
+      }
+    }
+  }
+}
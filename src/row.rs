@@ -4,66 +4,131 @@ A row of a system of linear Diophantine equations.
 
 */
 
-use std::{
+use alloc::vec::Vec;
+use core::{
   cmp::{min, Ordering},
   fmt::Display,
 };
 
-use crate::{Select, Soluble};
+use crate::{solubility::SolubilityVector, DiophantineInt, Select};
 
-///	Structure for each row. We have a pair of member functions to handle
-///	making a selection from a multiset, both normally and in the presence
-///	of solubility constraints on the non-selected part.
-#[derive(Default, Debug)]
-pub(crate) struct Row {
+/// Structure for each row. We have a pair of member functions to handle
+/// making a selection from a multiset, both normally and in the presence
+/// of solubility constraints on the non-selected part.
+#[derive(Clone, Default, Debug)]
+pub(crate) struct Row<T: DiophantineInt> {
   pub(crate) name: u32,        // original position of row
-  pub(crate) coeff: u32,       // coefficient
-  pub(crate) min_size: u32,    // minimum acceptable sum
-  pub(crate) min_product: u32, // coeff * minSize
-  pub(crate) min_leave: i32,   // minimum sum that must be left for
+  pub(crate) coeff: T,         // coefficient
+  pub(crate) min_size: T,      // minimum acceptable sum
+  pub(crate) min_product: T,   // coeff * minSize
+  pub(crate) min_leave: T,     // minimum sum that must be left for
   // remaining rows
-  pub(crate) max_size: u32,    // maximum acceptable sum
-  pub(crate) max_product: u32, // coeff * maxSize
-  pub(crate) max_leave: i32,   // maximum sum that may be left for
+  pub(crate) max_size: T,      // maximum acceptable sum
+  pub(crate) max_product: T,   // coeff * maxSize
+  pub(crate) max_leave: T,     // maximum sum that may be left for
   // remaining rows
-  pub(crate) current_size: u32, // current size of selection from multiset
-  pub(crate) current_max_size: u32, // maximum size of selection from multiset
-  pub(crate) selection: Vec<Select>, // vector of values selected for this row
-  pub(crate) soluble: Vec<Soluble>, // solubility vector (complex systems only)
+  pub(crate) unbounded: bool,  // max_size was inserted as UNBOUNDED
+  pub(crate) zero: bool,       // constrained to sum to zero by solve_with_zero_rows()
+  pub(crate) inserted_min_size: T, // min_size as inserted, before any adjustment
+  pub(crate) inserted_max_size: T, // max_size as inserted, before any adjustment
+  pub(crate) cell_max: Vec<T>,  // upper bound on each entry of the row, by column; missing entries are unbounded
+  pub(crate) fixed: Vec<Option<T>>, // fixed value of each entry of the row, by column; missing entries are free
+  pub(crate) fixed_sum: T,      // sum of the fixed entries
+  pub(crate) cell_count_max: Option<usize>, // most nonzero entries the row may have
+  pub(crate) current_size: T,     // current size of selection from multiset
+  pub(crate) current_max_size: T, // maximum size of selection from multiset
+  pub(crate) selection: Vec<Select<T>>, // vector of values selected for this row
+  pub(crate) soluble: SolubilityVector<T>, // solubility vector (complex systems only)
 }
 
-impl Row {
-  ///	Find a selection from a multiset by undoing the previous selection until
-  ///	the selected amount of some element can be increased by one (without
-  ///	exceeding overall selection size). Then make up the size of the selection
-  ///	by selecting the earliest elements available.
-  pub fn multiset_select(&mut self, bag: &mut Vec<u32>, find_first: bool) -> bool {
+impl<T: DiophantineInt> Row<T> {
+  /// The upper bound on the part of the entry of this row in column `c` chosen by the search. A fixed entry has
+  /// nothing left to choose.
+  #[inline(always)]
+  pub fn cell_max(&self, c: usize) -> T {
+    if self.fixed_value(c).is_some() {
+      return T::ZERO;
+    }
+    self.cell_max.get(c).copied().unwrap_or(T::UNBOUNDED)
+  }
+
+  /// The value the entry of this row in column `c` is fixed to, if any.
+  #[inline(always)]
+  pub fn fixed_value(&self, c: usize) -> Option<T> {
+    self.fixed.get(c).copied().unwrap_or(None)
+  }
+
+  /// The entry of this row in column `c` of the current solution, including any fixed value.
+  #[inline(always)]
+  pub fn entry(&self, c: usize) -> T {
+    let s = &self.selection[c];
+    s.base + s.extra + self.fixed_value(c).unwrap_or(T::ZERO)
+  }
+
+  /// Moves the bounds and fixed values of the entries in column `c` to column `permute[c]`, or back again if `inverse`.
+  pub(crate) fn permute_columns(&mut self, permute: &[u32], inverse: bool) {
+    fn apply<V: Copy>(values: &mut Vec<V>, permute: &[u32], inverse: bool, default: V) {
+      if values.is_empty() {
+        return;
+      }
+      values.resize(permute.len(), default);
+      let old = values.clone();
+      for (c, &p) in permute.iter().enumerate() {
+        if inverse {
+          values[c] = old[p as usize];
+        } else {
+          values[p as usize] = old[c];
+        }
+      }
+    }
+    apply(&mut self.cell_max, permute, inverse, T::UNBOUNDED);
+    apply(&mut self.fixed, permute, inverse, None);
+  }
+
+  /// Whether the current selection respects the upper bounds on the entries of this row.
+  pub fn respects_cell_max(&self) -> bool {
+    if self.cell_max.is_empty() && self.fixed.is_empty() {
+      return true;
+    }
+    self.selection.iter()
+        .enumerate()
+        .all(|(c, s)| s.base + s.extra <= self.cell_max(c))
+  }
+
+  /// Whether the current selection, with the fixed entries, leaves no more nonzero entries in this row than allowed.
+  pub fn respects_cell_count_max(&self) -> bool {
+    self.cell_count_max.is_none_or(|max| (0..self.selection.len()).filter(|&c| self.entry(c) > T::ZERO).count() <= max)
+  }
+
+  /// Find a selection from a multiset by undoing the previous selection until
+  /// the selected amount of some element can be increased by one (without
+  /// exceeding overall selection size). Then make up the size of the selection
+  /// by selecting the earliest elements available.
+  pub fn multiset_select(&mut self, bag: &mut [T], find_first: bool) -> bool {
     #[cfg(feature = "TRACE_CALLS")]
     println!("multiset_select");
-    let mut undone: i32 = 0;
+    let mut undone: T = T::ZERO;
     let mut forwards: bool = false; // A flag directing control flow.
 
     if !find_first {
-      if self.current_size > 0 {
-        undone = 0;
-
-        for j in 0..bag.len() {
-          assert!(self.selection[j].extra <= self.selection[j].max_extra);
-          let t = self.selection[j].extra;
-
-          if undone > 0 && t < self.selection[j].max_extra {
-            self.selection[j].extra += 1;
-            undone -= 1;
-            bag[j] -= self.coeff;
+      if self.current_size > T::ZERO {
+        for (selection, b) in self.selection.iter_mut().zip(bag.iter_mut()) {
+          assert!(selection.extra <= selection.max_extra);
+          let t = selection.extra;
+
+          if undone > T::ZERO && t < selection.max_extra {
+            selection.extra += T::ONE;
+            undone -= T::ONE;
+            *b -= self.coeff;
             // Go to forwards section.
             forwards = true;
             break;
           }
 
-          if t > 0 {
-            self.selection[j].extra = 0;
-            undone += t as i32;
-            bag[j] += t * self.coeff;
+          if t > T::ZERO {
+            selection.extra = T::ZERO;
+            undone += t;
+            *b += t * self.coeff;
           }
         }
       }
@@ -72,106 +137,120 @@ impl Row {
         return false;
       }
     } else {
-      undone = self.current_size as i32;
+      undone = self.current_size;
     }
 
     // Forwards //
     let mut j: usize = 0;
-    while undone > 0 {
+    while undone > T::ZERO {
       assert!(j < bag.len());
 
-      let t: i32 = min(undone, self.selection[j].max_extra as i32);
-      if t > 0 {
-        self.selection[j].extra = t as u32;
+      let t: T = min(undone, self.selection[j].max_extra);
+      if t > T::ZERO {
+        self.selection[j].extra = t;
         undone -= t;
-        bag[j] -= t as u32 * self.coeff;
+        bag[j] -= t * self.coeff;
       }
 
       j += 1;
     }
 
-    return true;
+    true
   }
 
-  /*
-  fn multiset_complex_nonlocal(
-    &mut self,
-    bag: &mut Vec<u32>,
-    soluble: &mut Vec<Soluble>,
-    find_first: bool,
-  ) -> bool {
-    let mut undone: u32;
-    let bag_length = bag.len();
-
-    if !find_first {
-      if self.current_size > 0 {
-        undone = 0;
-        'backtrack: for j in 0..bag_length {
-          assert!(self.selection[j].extra <= self.selection[j].max_extra);
-          let t = self.selection[j].extra;
-
-          if undone > 0 && t < self.selection[j].max_extra {
-            let c = bag[j];
-
-            for e in 1..=undone {
-              assert!((t + e) <= (self.selection[j].max_extra));
-              let idx = c - self.coeff;
-              if soluble[idx as usize].min != Soluble::INSOLUBLE {
-                self.selection[j].extra = t + e;
-                bag[j] = idx;
-                undone -= e;
-                continue 'forwards;
-              }
-            }
-          }
-          if t > 0 {
-            self.selection[j].extra = 0;
-            undone += t;
-            bag[j] += t * self.coeff;
-          }
-        }
-      }
-      return false;
-    } else {
-      undone = self.current_size;
+  /// Find the next selection in lexicographic order whose size lies in
+  /// `[current_size, current_max_size]`, or the first one if `find_first`.
+  /// With `soluble`, what the selection leaves of each element must be
+  /// soluble by the remaining rows.
+  pub(crate) fn multiset_lex(&mut self, bag: &mut [T], soluble: Option<&SolubilityVector<T>>, find_first: bool) -> bool {
+    let n = bag.len();
+    // The most the elements from `j` on can add to the size of the selection.
+    let mut room: Vec<T> = vec![T::ZERO; n + 1];
+    for j in (0..n).rev() {
+      room[j] = room[j + 1] + self.selection[j].max_extra;
     }
 
-    'forwards: for j in 0..bag_length {
-      assert!(j < bag_length);
-      let t = self.selection[j].max_extra;
-      if t <= undone {
-        if t > 0 {
-          self.selection[j].extra = t;
-          undone -= t;
-          bag[j] -= t * self.coeff;
+    let mut j     : usize = if find_first { 0 } else { n - 1 };
+    let mut first : bool  = find_first;
+    let mut size  : T     = self.selection[..j].iter().fold(T::ZERO, |size, s| size + s.extra);
+    loop {
+      if self.next_lex_value(j, bag, soluble, first, size, room[j + 1]) {
+        if j + 1 == n {
+          return true;
         }
+        size += self.selection[j].extra;
+        j    += 1;
+        first = true;
       } else {
-        self.selection[j].extra = undone;
-        bag[j] -= undone * self.coeff;
-        undone = 0;
-        if soluble[bag[j] as usize].min == Soluble::INSOLUBLE {
-          continue 'backtrack;
+        if j == 0 {
+          return false;
         }
+        j    -= 1;
+        size -= self.selection[j].extra;
+        first = false;
+      }
+    }
+  }
+
+  /// Select the next amount of element `j` (the least possible amount if
+  /// `first`), given that `size` has been selected from the elements before
+  /// it and the elements after it can add at most `room`. Returns false,
+  /// with nothing selected, if there is none.
+  fn next_lex_value(
+    &mut self,
+    j: usize,
+    bag: &mut [T],
+    soluble: Option<&SolubilityVector<T>>,
+    first: bool,
+    size: T,
+    room: T
+  ) -> bool {
+    let coeff            = self.coeff;
+    let current_size     = self.current_size;
+    let current_max_size = self.current_max_size;
+    let last             = j + 1 == bag.len();
+    let s                = &mut self.selection[j];
+
+    let mut value = if !first {
+      s.extra + T::ONE
+    } else if current_size > size + room {
+      // The later elements cannot make up the size on their own.
+      current_size - size - room
+    } else {
+      T::ZERO
+    };
+    bag[j] += s.extra * coeff;
+    s.extra = T::ZERO;
+
+    while value <= s.max_extra && size + value <= current_max_size {
+      let left = bag[j] - value * coeff;
+      if (!last || size + value >= current_size)
+          && soluble.is_none_or(|soluble| soluble.is_soluble(left.to_usize()))
+      {
+        s.extra = value;
+        bag[j]  = left;
+        return true;
       }
+      value += T::ONE;
     }
-    return true;
+    false
   }
-  */
 
   /// Find a selection from a multiset by undoing the previous selection until
   /// the selected amount of some element can be increased by one (without
   /// exceeding overall selection size or violating solubility constraints).
   /// Then make up the size of the selection by selecting the earliest elements
   /// available (backtracking if this violates solubility constraints).
+  #[allow(clippy::never_loop)]
   pub(crate) fn multiset_complex(
     &mut self,
-    bag: &mut Vec<u32>,
-    soluble: &mut Vec<Soluble>,
+    bag: &mut [T],
+    soluble: &SolubilityVector<T>,
     mut find_first: bool,
   ) -> bool {
     #[cfg(feature = "TRACE_CALLS")]
     println!("multiset_complex");
-    let mut undone: u32;
+    let mut undone: T;
     let bag_length = bag.len();
 
     // The control flow here is bananas, because Maude uses `GOTO`, which is considered bad.
@@ -179,8 +258,8 @@ impl Row {
     if find_first {
       undone = self.current_size;
     } else {
-      if self.current_size > 0 {
-        undone = 0;
+      if self.current_size > T::ZERO {
+        undone = T::ZERO;
         // How to skip the forward block in this case? `!find_first` is true, so we use `find_first` as a flag.
       } else {
         // The case `!find_first && self.current_size == 0`:
@@ -202,11 +281,11 @@ impl Row {
 
         // The FORWARD block //
         let mut j = 0;
-        while undone > 0 {
+        while undone > T::ZERO {
           assert!(j < bag_length);
           let t = self.selection[j].max_extra;
           if t <= undone {
-            if t > 0 {
+            if t > T::ZERO {
               self.selection[j].extra = t;
               undone -= t;
               bag[j] -= t * self.coeff;
@@ -214,8 +293,8 @@ impl Row {
           } else {
             self.selection[j].extra = undone;
             bag[j] -= undone * self.coeff;
-            undone = 0;
-            if soluble[bag[j] as usize].min == Soluble::INSOLUBLE {
+            undone = T::ZERO;
+            if !soluble.is_soluble(bag[j].to_usize()) {
               // Jump to the second half of the outer loop, which contains the backtrack block.
               break 'backtrack; // Same as `goto BACKTRACK block`
             }
@@ -228,31 +307,31 @@ impl Row {
       }
 
       // The BACKTRACK block //
-      for j in 0..bag_length {
-        assert!(self.selection[j].extra <= self.selection[j].max_extra);
-        let t = self.selection[j].extra;
+      for (selection, b) in self.selection.iter_mut().zip(bag.iter_mut()) {
+        assert!(selection.extra <= selection.max_extra);
+        let t = selection.extra;
 
-        if undone > 0 && t < self.selection[j].max_extra {
-          let mut c = bag[j];
+        if undone > T::ZERO && t < selection.max_extra {
+          let mut c = *b;
 
-          let mut e = 1;
+          let mut e = T::ONE;
           while e <= undone {
             // for e in 1..=undone {
-            assert!((t + e) <= (self.selection[j].max_extra));
+            assert!((t + e) <= (selection.max_extra));
             c -= self.coeff;
-            if soluble[c as usize].min != Soluble::INSOLUBLE {
-              self.selection[j].extra = t + e;
-              bag[j] = c;
+            if soluble.is_soluble(c.to_usize()) {
+              selection.extra = t + e;
+              *b = c;
               undone -= e;
               continue 'forward; // Same as `goto FORWARD block`
             }
-            e += 1;
+            e += T::ONE;
           }
         }
-        if t > 0 {
-          self.selection[j].extra = 0;
+        if t > T::ZERO {
+          selection.extra = T::ZERO;
           undone += t;
-          bag[j] += t * self.coeff;
+          *b += t * self.coeff;
         }
       }
       // If we fall through backtrack we return false
@@ -264,48 +343,52 @@ impl Row {
   }
 }
 
-impl Ord for Row {
+/// Rows are searched in descending order of `coeff`, which `viable()` relies on: a row can only take from columns at
+/// least its coefficient, all of which are also open to the rows after it. Ties are broken by ascending `max_size`, so
+/// that the last row is the one most likely to take whatever is left, and then by `name`, so that the order is total
+/// and rows alike in both keep their insertion order whatever the sort.
+impl<T: DiophantineInt> Ord for Row<T> {
   fn cmp(&self, other: &Self) -> Ordering {
-    let t: Ordering = self.coeff.cmp(&other.coeff);
+    let t: Ordering = other.coeff.cmp(&self.coeff);
 
     if t == Ordering::Equal {
-      // Break ties with `max_size`.
-      self.max_size.cmp(&other.max_size)
+      // Break ties with `max_size`, then with the insertion index.
+      self.max_size.cmp(&other.max_size).then_with(|| self.name.cmp(&other.name))
     } else {
       t
     }
   }
 }
 
-impl PartialOrd for Row {
+impl<T: DiophantineInt> PartialOrd for Row<T> {
   fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
     Some(self.cmp(other))
   }
 }
 
-impl Eq for Row {}
+impl<T: DiophantineInt> Eq for Row<T> {}
 
-impl PartialEq for Row {
+impl<T: DiophantineInt> PartialEq for Row<T> {
   fn eq(&self, other: &Self) -> bool {
-    self.coeff == other.coeff && self.max_size == other.max_size
+    self.coeff == other.coeff && self.max_size == other.max_size && self.name == other.name
   }
 }
 
-impl Display for Row {
-  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl<T: DiophantineInt> Display for Row<T> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
     write!(f, "\tname: {}\n\tcoeff: {}\n\tminSize: {}\n\tminProduct: {}\n\tminLeave: {}\n\tmaxSize: {}\n\tmaxProduct: {}\n\tmaxLeave: {}\n\tcurrentSize: {}\n\tcurrentMaxSize: {}\n\tselection: [",
-    self.name, self.coeff, self.min_size, self.min_product, self.min_leave, self.max_size, self.max_product, self.max_leave, self.current_size, self.current_size)?;
+    self.name, self.coeff, self.min_size, self.min_product, self.min_leave, self.max_size, self.max_product, self.max_leave, self.current_size, self.current_max_size)?;
     for sel in &self.selection {
       write!(f, "{{{}}} ", sel.base)?;
-      if sel.extra != 0 {
+      if sel.extra != T::ZERO {
         write!(f, "+ {} extra, {} maxExtra", sel.extra, sel.max_extra)?;
       }
       write!(f, ", ")?;
     }
     write!(f, "]\n\tsoluble: [")?;
-    for sol in &self.soluble {
+    for sol in self.soluble.to_vec().iter() {
       write!(f, "{{{},{}}} ", sol.min, sol.max)?;
     }
-    write!(f, "]\n")
+    writeln!(f, "]")
   }
 }
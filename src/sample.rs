@@ -0,0 +1,83 @@
+/*!
+
+Drawing random solutions without enumerating them.
+
+A solution is built one entry at a time, in row-major insertion order, by fixing each entry with `fix_cell` to a value
+chosen at random among those for which the system still has a solution. Every entry is then chosen uniformly from its
+feasible values, which always leads to a solution, but the solutions themselves are not drawn uniformly: a value that
+leaves few ways to complete the solution is as likely as one that leaves many. Uniform sampling would need the number
+of completions of each choice, which is as hard as counting the solutions. Each feasibility check is a search for one
+solution, so drawing a sample costs up to one such search per candidate value of each entry, however many solutions
+there are. Only the candidates tried are stored, so an entry with a huge range of values costs no more memory than one
+with a few, though an entry is drawn from at most its first `usize::MAX` values.
+
+The crate has no dependencies, so the source of randomness is a closure `random(n)` returning an index in `0..n`. With
+the `rand` crate, that is `|n| rng.gen_range(0..n)`.
+
+*/
+
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::convert::TryFrom;
+
+use crate::{DiophantineInt, DiophantineSystem, SystemSpec};
+
+impl<T: DiophantineInt> DiophantineSystem<T> {
+  /// A random solution of the system as it was built, as a matrix with rows in insertion order like
+  /// `solution_matrix()`, or `None` if the system has no solutions. `random(n)` must return an index in `0..n`. Each
+  /// entry is chosen uniformly among the values that leave the rest of the system soluble, so solutions are drawn
+  /// with different probabilities; see the module documentation. Leaves the system untouched.
+  pub fn sample_solution<R: FnMut(usize) -> usize>(&self, mut random: R) -> Option<Vec<Vec<T>>> {
+    let mut spec    = self.spec();
+    let     soluble = |spec: &SystemSpec<T>| spec.to_system().is_ok_and(|mut system| system.solve());
+    if !soluble(&spec) {
+      return None;
+    }
+
+    for r in 0..spec.rows.len() {
+      for c in 0..spec.columns.len() {
+        if spec.fixed.iter().any(|&(row, col, _)| row == r && col == c) {
+          continue;
+        }
+
+        // The most the entry can be, as far as its column, its own bound, and the row bound tell.
+        let row = spec.rows[r];
+        let mut upper = spec.columns[c] / row.coeff;
+        if row.max_size != T::UNBOUNDED {
+          upper = upper.min(row.max_size);
+        }
+        for &(_, _, max) in spec.cell_max.iter().filter(|&&(row, col, _)| row == r && col == c) {
+          upper = upper.min(max);
+        }
+
+        // Try the candidates in random order; the first that leaves the system soluble is uniform among those that do.
+        // The order is a Fisher-Yates shuffle of `0..=upper` carried only as far as needed, with the candidates moved
+        // out of place kept in a map, so the range can be far larger than the number of candidates tried.
+        let count = upper
+            .to_i64()
+            .and_then(|upper| usize::try_from(upper).ok())
+            .map_or(usize::MAX, |upper| upper.saturating_add(1));
+        let mut moved = BTreeMap::new();
+        spec.fixed.push((r, c, T::ZERO));
+        for k in 0..count {
+          let j         = k + random(count - k);
+          let candidate = moved.get(&j).copied().unwrap_or(j);
+          let displaced = moved.remove(&k).unwrap_or(k);
+          if j != k {
+            moved.insert(j, displaced);
+          }
+          spec.fixed.last_mut().unwrap().2 = T::from_usize(candidate);
+          if soluble(&spec) {
+            break;
+          }
+        }
+      }
+    }
+
+    // Every entry is now fixed.
+    let mut matrix = vec![vec![T::ZERO; spec.columns.len()]; spec.rows.len()];
+    for &(r, c, value) in spec.fixed.iter() {
+      matrix[r][c] = value;
+    }
+    Some(matrix)
+  }
+}
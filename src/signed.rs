@@ -0,0 +1,306 @@
+/*!
+
+A solver for general linear Diophantine systems $R \cdot M = C$ over the naturals, in which the coefficients $R$ and
+the columns $C$ may be negative.
+
+The solubility vectors of `DiophantineSystem` step through the column values by `coeff`, which relies on every
+coefficient being positive, so signed systems are solved by a separate, plainer search. The restrictions are:
+
+ * Every row must have a finite `max_size`. With negative coefficients, contributions can cancel, so a row bounded
+   only by the column total could take arbitrarily much and the system could have infinitely many solutions.
+ * The entries of $M$ are chosen one at a time, row by row, pruning a partial solution as soon as some column can no
+   longer be reached by the remaining rows, and the last row is solved for directly. There are no solubility vectors,
+   so the cost grows with the product of the rows' `max_size` rather than with the column values. Prefer
+   `DiophantineSystem` whenever the coefficients are positive.
+
+Rows are not reordered, so solutions are produced in the lexicographic order of $M$ read in row-major order, with
+the last row determined by the others.
+
+```rust,no_run
+# use diophantine::{DiophantineError, SignedDiophantineSystem};
+# fn main() -> Result<(), DiophantineError> {
+// 3x - 2y == 1, with x and y each the sum of between 1 and 4 constants.
+let mut system = SignedDiophantineSystem::new(2, 1);
+system.insert_row(3, 1, 4)?;
+system.insert_row(-2, 1, 4)?;
+system.insert_column(1)?;
+while system.solve() {
+  println!("x = {}, y = {}", system.solution(0, 0)?, system.solution(1, 0)?);
+}
+# Ok(())
+# }
+```
+
+*/
+
+use alloc::vec::Vec;
+
+use crate::{DiophantineError, SignedDiophantineInt};
+
+/// A system of linear Diophantine equations with signed coefficients and columns over the signed integer type `T`,
+/// which defaults to `i64`. The interface mirrors that of `DiophantineSystem`, except that every row must have a
+/// finite `max_size`. The search is a plain backtracking search whose cost grows with the product of the rows'
+/// `max_size`, so prefer `DiophantineSystem` whenever the coefficients are positive.
+#[derive(Clone, Debug)]
+pub struct SignedDiophantineSystem<T: SignedDiophantineInt = i64> {
+  rows      : Vec<(T, T, T)>, // (coeff, min_size, max_size) in insertion order
+  columns   : Vec<T>,         // column values as inserted
+  residual  : Vec<T>,         // what is left of each column for the rows not yet assigned
+  cells     : Vec<T>,         // the solution matrix in row-major order
+  row_sums  : Vec<T>,         // sum of the assigned entries of each row
+  low       : Vec<T>,         // least contribution rows `i..` can make to a single column
+  high      : Vec<T>,         // greatest contribution rows `i..` can make to a single column
+  closed    : bool,
+  failed    : bool,
+}
+
+impl SignedDiophantineSystem<i64> {
+  pub fn new(row_count: usize, col_count: usize) -> Self {
+    SignedDiophantineSystem::with_capacity(row_count, col_count)
+  }
+}
+
+impl<T: SignedDiophantineInt> SignedDiophantineSystem<T> {
+  /// Creates an empty system over the integer type `T` with room for the given number of rows and columns.
+  pub fn with_capacity(row_count: usize, col_count: usize) -> Self {
+    SignedDiophantineSystem {
+      rows     : Vec::with_capacity(row_count),
+      columns  : Vec::with_capacity(col_count),
+      residual : Vec::new(),
+      cells    : Vec::new(),
+      row_sums : Vec::new(),
+      low      : Vec::new(),
+      high     : Vec::new(),
+      closed   : false,
+      failed   : false,
+    }
+  }
+
+  /// Adds a row with nonzero coefficient `coeff` whose sum must lie in `[min_size, max_size]`, where
+  /// `0 <= min_size <= max_size` and `max_size` is not `UNBOUNDED`. Fails with `DiophantineError::Overflow` if
+  /// `coeff * max_size`, the sum of such products, or what is left of a column after them does not fit in `T`.
+  pub fn insert_row(&mut self, coeff: T, min_size: T, max_size: T) -> Result<(), DiophantineError> {
+    if self.closed {
+      return Err(DiophantineError::SystemClosed);
+    }
+    if coeff == T::ZERO || min_size < T::ZERO || min_size > max_size || max_size == T::UNBOUNDED {
+      return Err(DiophantineError::InvalidRow);
+    }
+    self.rows.push((coeff, min_size, max_size));
+    let fits = match contribution_range(&self.rows) {
+      Some((low, high)) => self.columns.iter().all(|&column| residual_fits(column, low, high)),
+      None              => false,
+    };
+    if !fits {
+      self.rows.pop();
+      return Err(DiophantineError::Overflow);
+    }
+    Ok(())
+  }
+
+  /// Adds a column with value `value`, which may be negative. Fails with `DiophantineError::Overflow` if what is
+  /// left of it after the rows' contributions does not fit in `T`.
+  pub fn insert_column(&mut self, value: T) -> Result<(), DiophantineError> {
+    if self.closed {
+      return Err(DiophantineError::SystemClosed);
+    }
+    // `insert_row` has checked the range.
+    let (low, high) = contribution_range(&self.rows).unwrap();
+    if !residual_fits(value, low, high) {
+      return Err(DiophantineError::Overflow);
+    }
+    self.columns.push(value);
+    Ok(())
+  }
+
+  #[inline(always)]
+  pub fn row_count(&self) -> usize {
+    self.rows.len()
+  }
+
+  #[inline(always)]
+  pub fn column_count(&self) -> usize {
+    self.columns.len()
+  }
+
+  /// The value of $M_{r,c}$ in the current solution.
+  pub fn solution(&self, r: usize, c: usize) -> Result<T, DiophantineError> {
+    if !self.closed {
+      return Err(DiophantineError::NoSolutionYet);
+    }
+    if self.failed {
+      return Err(DiophantineError::Exhausted);
+    }
+    if r >= self.rows.len() || c >= self.columns.len() {
+      return Err(DiophantineError::IndexOutOfRange {
+        row       : r,
+        col       : c,
+        row_count : self.rows.len(),
+        col_count : self.columns.len(),
+      });
+    }
+    Ok(self.solution_unchecked(r, c))
+  }
+
+  /// Same as `solution()`, but panics instead of returning an error.
+  #[inline(always)]
+  pub fn solution_unchecked(&self, r: usize, c: usize) -> T {
+    assert!(self.closed, "solve() not called");
+    assert!(!self.failed, "non-existent soluiton");
+    assert!(c < self.columns.len(), "column index out of range");
+    self.cells[r * self.columns.len() + c]
+  }
+
+  /// Finds the next solution, returning false if there are no more. As with `DiophantineSystem`, a system without rows
+  /// or without columns has the empty matrix as its only solution when every column is zero and every row's `min_size`
+  /// is zero, and no solution otherwise.
+  pub fn solve(&mut self) -> bool {
+    let find_first = !self.closed;
+    if find_first {
+      self.precompute();
+    }
+    if self.failed {
+      return false;
+    }
+
+    let found = if self.rows.is_empty() || self.columns.is_empty() {
+      find_first
+          && self.columns.iter().all(|&column| column == T::ZERO)
+          && self.rows.iter().all(|&(_, min_size, _)| min_size == T::ZERO)
+    } else {
+      self.search(find_first)
+    };
+    if !found {
+      self.failed = true;
+    }
+    found
+  }
+
+  /// `insert_row` and `insert_column` rule out overflow of the products, their sums and the residuals.
+  fn precompute(&mut self) {
+    self.closed = true;
+    self.residual = self.columns.clone();
+    self.cells    = vec![T::ZERO; self.rows.len() * self.columns.len()];
+    self.row_sums = vec![T::ZERO; self.rows.len()];
+
+    self.low  = vec![T::ZERO; self.rows.len() + 1];
+    self.high = vec![T::ZERO; self.rows.len() + 1];
+    for (i, &(coeff, _, max_size)) in self.rows.iter().enumerate().rev() {
+      let product = coeff * max_size;
+      self.low[i]  = self.low[i + 1];
+      self.high[i] = self.high[i + 1];
+      if coeff < T::ZERO {
+        self.low[i] += product;
+      } else {
+        self.high[i] += product;
+      }
+    }
+  }
+
+  /// Advances to the next assignment of the rows other than the last for which the last row has a solution.
+  fn search(&mut self, find_first: bool) -> bool {
+    let last = (self.rows.len() - 1) * self.columns.len(); // first cell of the last row
+    if last == 0 {
+      return find_first && self.solve_last_row();
+    }
+
+    let mut p     = if find_first { 0 } else { last - 1 };
+    let mut first = find_first;
+    loop {
+      if self.next_value(p, first) {
+        if p + 1 == last {
+          if self.solve_last_row() {
+            return true;
+          }
+          first = false;
+        } else {
+          p    += 1;
+          first = true;
+        }
+      } else {
+        if p == 0 {
+          return false;
+        }
+        p    -= 1;
+        first = false;
+      }
+    }
+  }
+
+  /// Sets cell `p` to its next value (its first value if `first`) for which the partial solution can still be
+  /// completed as far as its column and row are concerned. Returns false, with the cell cleared, if there is none.
+  fn next_value(&mut self, p: usize, first: bool) -> bool {
+    let column_count = self.columns.len();
+    let (r, c) = (p / column_count, p % column_count);
+    let (coeff, min_size, max_size) = self.rows[r];
+
+    // Take back the current value.
+    let old = if first { T::ZERO } else { self.cells[p] };
+    self.residual[c] += coeff * old;
+    self.row_sums[r] -= old;
+
+    let mut value = if first { T::ZERO } else { old + T::ONE };
+    while self.row_sums[r] + value <= max_size {
+      let residual = self.residual[c] - coeff * value;
+      // Increasing the value moves the residual away from what the remaining rows can make up.
+      if (coeff > T::ZERO && residual < self.low[r + 1]) || (coeff < T::ZERO && residual > self.high[r + 1]) {
+        break;
+      }
+
+      if residual <= self.high[r + 1]
+          && residual >= self.low[r + 1]
+          && (c + 1 < column_count || self.row_sums[r] + value >= min_size)
+      {
+        self.cells[p]     = value;
+        self.residual[c]  = residual;
+        self.row_sums[r] += value;
+        return true;
+      }
+      value += T::ONE;
+    }
+
+    self.cells[p] = T::ZERO;
+    false
+  }
+
+  /// Solves the last row for what is left of the columns. Returns false if that has no solution.
+  fn solve_last_row(&mut self) -> bool {
+    let column_count = self.columns.len();
+    let r = self.rows.len() - 1;
+    let (coeff, min_size, max_size) = self.rows[r];
+
+    let mut sum = T::ZERO;
+    for c in 0..column_count {
+      let value = self.residual[c] / coeff;
+      if value < T::ZERO || value * coeff != self.residual[c] {
+        return false;
+      }
+      self.cells[r * column_count + c] = value;
+      sum += value;
+    }
+    sum >= min_size && sum <= max_size
+  }
+}
+
+
+/// The least and greatest contributions `rows` can make to a single column, or `None` if they do not fit in `T`.
+fn contribution_range<T: SignedDiophantineInt>(rows: &[(T, T, T)]) -> Option<(T, T)> {
+  let mut low  = T::ZERO;
+  let mut high = T::ZERO;
+  for &(coeff, _, max_size) in rows.iter() {
+    let product = coeff.checked_mul(max_size)?;
+    if coeff < T::ZERO {
+      low = low.checked_add(product)?;
+    } else {
+      high = high.checked_add(product)?;
+    }
+  }
+  Some((low, high))
+}
+
+/// Whether what is left of `column` after any contribution in `[low, high]` fits in `T`, and so does its negation,
+/// which rules out dividing the least value of `T` by -1 when solving the last row.
+fn residual_fits<T: SignedDiophantineInt>(column: T, low: T, high: T) -> bool {
+  let negate = |value: T| value.checked_mul(T::ZERO - T::ONE);
+  [low, high].iter().all(|&bound| negate(bound).and_then(|bound| column.checked_add(bound)).and_then(negate).is_some())
+}
@@ -0,0 +1,205 @@
+/*!
+
+Storage for the solubility vectors of a complex system, one entry per column value for each row (see `Soluble`).
+
+By default each entry is a `Soluble<T>`. With the `compact_solubility` feature, the least and greatest amounts are kept
+in separate arrays of `u32` instead. This halves the memory taken by systems over `u64` and more than that over
+`u128`, and the checks made during the search, which only ask whether a column value is soluble, read the array of
+least amounts alone. An amount never exceeds the largest column value, and neither does the length of a vector, so
+`u32` suffices for any solubility vector that fits in memory. A system whose largest column does not fit in a `u32` is
+reported as too large. The solutions are the same either way.
+
+The vectors depend only on the rows and the largest column value, so `SolubilityTables` carries them from one system
+to others with the same rows.
+
+*/
+
+use alloc::vec::Vec;
+#[cfg(feature = "compact_solubility")]
+use core::marker::PhantomData;
+
+use crate::{DiophantineInt, Soluble};
+
+/// The solubility vectors of a complex system, taken with `DiophantineSystem::extract_solubility()` and attached to
+/// another system with the same rows by `DiophantineSystem::with_solubility()`, which then skips building its own if
+/// its largest column is at most `max_column_value()`.
+#[derive(Clone, Debug)]
+pub struct SolubilityTables<T: DiophantineInt = u32> {
+  pub(crate) rows    : Vec<(u32, T, T)>, // (name, coeff, max_size) of the rows in search order, UNBOUNDED if unbounded
+  pub(crate) vectors : Vec<SolubilityVector<T>>, // in search order
+}
+
+impl<T: DiophantineInt> SolubilityTables<T> {
+  /// The largest column value the tables cover.
+  pub fn max_column_value(&self) -> T {
+    T::from_usize(self.vectors[0].len() - 1)
+  }
+
+  /// The number of rows of the system the tables were taken from.
+  pub fn row_count(&self) -> usize {
+    self.rows.len()
+  }
+}
+
+/// The solubility vector of a row, indexed by column value.
+#[derive(Clone, Default, Debug)]
+pub(crate) struct SolubilityVector<T: DiophantineInt> {
+  #[cfg(not(feature = "compact_solubility"))]
+  entries : Vec<Soluble<T>>,
+  #[cfg(feature = "compact_solubility")]
+  min     : Vec<u32>, // least amounts, `u32::MAX` for INSOLUBLE
+  #[cfg(feature = "compact_solubility")]
+  max     : Vec<u32>, // greatest amounts, `u32::MAX` for INSOLUBLE
+  #[cfg(feature = "compact_solubility")]
+  phantom : PhantomData<T>,
+}
+
+#[cfg(not(feature = "compact_solubility"))]
+impl<T: DiophantineInt> SolubilityVector<T> {
+  /// The memory taken by each entry.
+  pub(crate) const ENTRY_BYTES: usize = core::mem::size_of::<Soluble<T>>();
+
+  /// Whether vectors up to `max_column_value` can be stored at all.
+  #[inline(always)]
+  pub(crate) fn can_hold(_max_column_value: T) -> bool {
+    true
+  }
+
+  #[inline(always)]
+  pub(crate) fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  #[inline(always)]
+  pub(crate) fn truncate(&mut self, length: usize) {
+    self.entries.truncate(length);
+  }
+
+  pub(crate) fn shrink_to_fit(&mut self) {
+    self.entries.shrink_to_fit();
+  }
+
+  /// Makes room for `length` entries, returning false if the memory cannot be allocated.
+  pub(crate) fn try_reserve(&mut self, length: usize) -> bool {
+    self.entries.try_reserve(length.saturating_sub(self.entries.len())).is_ok()
+  }
+
+  /// Makes the vector `length` entries long, every one of them `INSOLUBLE`.
+  pub(crate) fn reset(&mut self, length: usize) {
+    self.entries.clear();
+    self.entries.resize(length, Soluble::INSOLUBLE_STRUCT);
+  }
+
+  #[inline(always)]
+  pub(crate) fn is_soluble(&self, v: usize) -> bool {
+    self.entries[v].min != Soluble::INSOLUBLE
+  }
+
+  #[inline(always)]
+  pub(crate) fn min(&self, v: usize) -> T {
+    self.entries[v].min
+  }
+
+  #[inline(always)]
+  pub(crate) fn max(&self, v: usize) -> T {
+    self.entries[v].max
+  }
+
+  #[inline(always)]
+  pub(crate) fn set_min(&mut self, v: usize, min: T) {
+    self.entries[v].min = min;
+  }
+
+  #[inline(always)]
+  pub(crate) fn set_max(&mut self, v: usize, max: T) {
+    self.entries[v].max = max;
+  }
+
+  /// The entries as `Soluble` values.
+  pub(crate) fn to_vec(&self) -> Vec<Soluble<T>> {
+    self.entries.clone()
+  }
+}
+
+#[cfg(feature = "compact_solubility")]
+impl<T: DiophantineInt> SolubilityVector<T> {
+  /// The memory taken by each entry.
+  pub(crate) const ENTRY_BYTES: usize = 2 * core::mem::size_of::<u32>();
+
+  /// Whether vectors up to `max_column_value` can be stored at all: every amount, and the length, must fit below the
+  /// `u32::MAX` that marks an insoluble value.
+  #[inline(always)]
+  pub(crate) fn can_hold(max_column_value: T) -> bool {
+    max_column_value.to_usize() < u32::MAX as usize
+  }
+
+  #[inline(always)]
+  pub(crate) fn len(&self) -> usize {
+    self.min.len()
+  }
+
+  #[inline(always)]
+  pub(crate) fn truncate(&mut self, length: usize) {
+    self.min.truncate(length);
+    self.max.truncate(length);
+  }
+
+  pub(crate) fn shrink_to_fit(&mut self) {
+    self.min.shrink_to_fit();
+    self.max.shrink_to_fit();
+  }
+
+  /// Makes room for `length` entries, returning false if the memory cannot be allocated.
+  pub(crate) fn try_reserve(&mut self, length: usize) -> bool {
+    let additional = length.saturating_sub(self.len());
+    self.min.try_reserve(additional).is_ok() && self.max.try_reserve(additional).is_ok()
+  }
+
+  /// Makes the vector `length` entries long, every one of them `INSOLUBLE`.
+  pub(crate) fn reset(&mut self, length: usize) {
+    self.min.clear();
+    self.min.resize(length, u32::MAX);
+    self.max.clear();
+    self.max.resize(length, u32::MAX);
+  }
+
+  #[inline(always)]
+  pub(crate) fn is_soluble(&self, v: usize) -> bool {
+    self.min[v] != u32::MAX
+  }
+
+  #[inline(always)]
+  pub(crate) fn min(&self, v: usize) -> T {
+    Self::widen(self.min[v])
+  }
+
+  #[inline(always)]
+  pub(crate) fn max(&self, v: usize) -> T {
+    Self::widen(self.max[v])
+  }
+
+  #[inline(always)]
+  pub(crate) fn set_min(&mut self, v: usize, min: T) {
+    self.min[v] = Self::narrow(min);
+  }
+
+  #[inline(always)]
+  pub(crate) fn set_max(&mut self, v: usize, max: T) {
+    self.max[v] = Self::narrow(max);
+  }
+
+  /// The entries as `Soluble` values.
+  pub(crate) fn to_vec(&self) -> Vec<Soluble<T>> {
+    (0..self.len()).map(|v| Soluble { min: self.min(v), max: self.max(v) }).collect()
+  }
+
+  #[inline(always)]
+  fn narrow(amount: T) -> u32 {
+    if amount == Soluble::INSOLUBLE { u32::MAX } else { amount.to_usize() as u32 }
+  }
+
+  #[inline(always)]
+  fn widen(amount: u32) -> T {
+    if amount == u32::MAX { Soluble::INSOLUBLE } else { T::from_usize(amount as usize) }
+  }
+}
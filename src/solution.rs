@@ -0,0 +1,124 @@
+/*!
+
+Owned snapshots of solutions, and an iterator that enumerates them.
+
+*/
+
+use alloc::vec::Vec;
+use core::iter::FusedIterator;
+
+use crate::{DiophantineInt, DiophantineSystem};
+
+/// A snapshot of the solution matrix $M$ taken after a successful call to `solve()`. Rows are in the order in which
+/// they were inserted with `insert_row`, and columns in the order in which they were inserted with `insert_column`.
+/// Snapshots compare and hash by their matrix, so the solutions of different systems, or found in a different order,
+/// can be collected into a `HashSet` to remove duplicates.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Solution<T: DiophantineInt = u32> {
+  row_count    : usize,
+  column_count : usize,
+  cells        : Vec<T>, // row-major
+}
+
+impl<T: DiophantineInt> Solution<T> {
+  pub(crate) fn new(row_count: usize, column_count: usize, cells: Vec<T>) -> Self {
+    assert_eq!(cells.len(), row_count * column_count);
+    Solution {
+      row_count,
+      column_count,
+      cells,
+    }
+  }
+
+  /// The value of $M_{r,c}$.
+  #[inline(always)]
+  pub fn get(&self, r: usize, c: usize) -> T {
+    assert!(c < self.column_count, "column index out of range");
+    self.cells[r * self.column_count + c]
+  }
+
+  /// Row `r` of $M$, that is, the multiplicities assigned to the variable of the `r`th row.
+  #[inline(always)]
+  pub fn row(&self, r: usize) -> &[T] {
+    &self.cells[r * self.column_count..(r + 1) * self.column_count]
+  }
+
+  /// A deterministic 64-bit hash of the matrix, the same as `DiophantineSystem::solution_key` for the solution this
+  /// is a snapshot of. Unlike the `Hash` implementation, it does not depend on the hasher, the platform, or the
+  /// integer type.
+  pub fn key(&self) -> u64 {
+    solution_key(self.row_count, self.column_count, self.cells.iter().cloned())
+  }
+
+  #[inline(always)]
+  pub fn row_count(&self) -> usize {
+    self.row_count
+  }
+
+  #[inline(always)]
+  pub fn column_count(&self) -> usize {
+    self.column_count
+  }
+}
+
+/// The 64-bit FNV-1a hash of the dimensions and entries of a solution matrix given in row-major order. An entry less
+/// than `u64::MAX` is hashed as one word, and any other as `u64::MAX` followed by its low and high 64 bits, so that
+/// the hash depends only on the values of the entries, whatever their type.
+pub(crate) fn solution_key<T: DiophantineInt>(
+  row_count    : usize,
+  column_count : usize,
+  cells        : impl Iterator<Item = T>,
+) -> u64 {
+  let dimensions = [row_count as u64, column_count as u64];
+  let words      = dimensions.iter().cloned().chain(cells.flat_map(|cell| {
+    let value  = cell.to_u128();
+    let wide   = value >= u64::MAX as u128;
+    let first  = if wide { u64::MAX } else { value as u64 };
+    let halves = if wide { (Some(value as u64), Some((value >> 64) as u64)) } else { (None, None) };
+    Some(first).into_iter().chain(halves.0).chain(halves.1)
+  }));
+  fnv1a(words)
+}
+
+/// The 64-bit FNV-1a hash of `words`, each taken as its eight little-endian bytes.
+pub(crate) fn fnv1a(words: impl Iterator<Item = u64>) -> u64 {
+  const OFFSET_BASIS : u64 = 0xcbf2_9ce4_8422_2325;
+  const PRIME        : u64 = 0x0100_0000_01b3;
+
+  words.fold(OFFSET_BASIS, |hash, word| {
+    word.to_le_bytes().iter().fold(hash, |hash, &byte| (hash ^ byte as u64).wrapping_mul(PRIME))
+  })
+}
+
+/// Iterator over the remaining solutions of a `DiophantineSystem`, created by `DiophantineSystem::solutions()`. Each
+/// call to `next()` drives `solve()` once, and `None` is returned exactly when `solve()` would return `false`.
+pub struct Solutions<'a, T: DiophantineInt = u32> {
+  system: &'a mut DiophantineSystem<T>,
+}
+
+impl<'a, T: DiophantineInt> Solutions<'a, T> {
+  pub(crate) fn new(system: &'a mut DiophantineSystem<T>) -> Self {
+    Solutions { system }
+  }
+}
+
+impl<'a, T: DiophantineInt> Iterator for Solutions<'a, T> {
+  type Item = Solution<T>;
+
+  fn next(&mut self) -> Option<Solution<T>> {
+    self.system.next_solution()
+  }
+}
+
+// Once the system is exhausted `next_solution()` keeps returning `None`.
+impl<'a, T: DiophantineInt> FusedIterator for Solutions<'a, T> {}
+
+/// `for solution in &mut system` is the same as `for solution in system.solutions()`.
+impl<'a, T: DiophantineInt> IntoIterator for &'a mut DiophantineSystem<T> {
+  type Item     = Solution<T>;
+  type IntoIter = Solutions<'a, T>;
+
+  fn into_iter(self) -> Solutions<'a, T> {
+    self.solutions()
+  }
+}
@@ -0,0 +1,62 @@
+/*!
+
+A plain-data description of a system as it was built with `insert_row`/`insert_column`, independent of any search
+state. Use it to persist a subproblem and rebuild an equivalent open system later.
+
+*/
+
+use alloc::vec::Vec;
+
+use crate::{DiophantineError, DiophantineInt, DiophantineSystem};
+
+/// The arguments of one call to `insert_row`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RowSpec<T: DiophantineInt = u32> {
+  pub coeff    : T,
+  pub min_size : T,
+  pub max_size : T,
+}
+
+/// The rows and columns of a system in insertion order, the index of its extension row if it has one, the bounds on
+/// its entries as `(row, column, max)` triples, its fixed entries as `(row, column, value)` triples, the limits on
+/// the number of nonzero entries of its rows as `(row, max)` pairs, and the rows constrained to sum to zero by
+/// `solve_with_zero_rows`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SystemSpec<T: DiophantineInt = u32> {
+  pub rows          : Vec<RowSpec<T>>,
+  pub columns       : Vec<T>,
+  pub extension_row : Option<usize>,
+  pub cell_max      : Vec<(usize, usize, T)>,
+  pub fixed         : Vec<(usize, usize, T)>,
+  pub cell_count_max: Vec<(usize, usize)>,
+  pub zero_rows     : Vec<usize>,
+}
+
+impl<T: DiophantineInt> SystemSpec<T> {
+  /// Builds an open system equivalent to the one described, as if by calling `insert_row`, `insert_column`,
+  /// `set_extension_row`, `set_cell_max`, `fix_cell` and `set_row_cell_count_max`, with the zero rows constrained as
+  /// `solve_with_zero_rows` would, but without solving.
+  pub fn to_system(&self) -> Result<DiophantineSystem<T>, DiophantineError> {
+    let mut system = DiophantineSystem::with_capacity(self.rows.len(), self.columns.len());
+    for row in self.rows.iter() {
+      system.insert_row(row.coeff, row.min_size, row.max_size)?;
+    }
+    for &column in self.columns.iter() {
+      system.insert_column(column)?;
+    }
+    if let Some(i) = self.extension_row {
+      system.set_extension_row(i)?;
+    }
+    for &(row, col, max) in self.cell_max.iter() {
+      system.set_cell_max(row, col, max)?;
+    }
+    for &(row, col, value) in self.fixed.iter() {
+      system.fix_cell(row, col, value)?;
+    }
+    for &(row, max) in self.cell_count_max.iter() {
+      system.set_row_cell_count_max(row, max)?;
+    }
+    system.constrain_zero_rows(&self.zero_rows)?;
+    Ok(system)
+  }
+}
@@ -0,0 +1,18 @@
+/*!
+
+Counters describing the work done by the search.
+
+*/
+
+/// Statistics accumulated by `solve()` since the system was created or last reset.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SolveStats {
+  /// Number of times a non-last row was solved, whether or not a selection for it was found.
+  pub nodes_visited   : u64,
+  /// Number of times a row failed and the search stepped back to the previous row.
+  pub backtracks      : u64,
+  /// Number of times `solve()` returned true.
+  pub solutions_found : u64,
+  /// Number of times a non-last row could only take nothing, so that its selection was made without a search.
+  pub empty_rows      : u64,
+}
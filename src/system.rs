@@ -6,7 +6,7 @@ Structure representing the system of Diophantine equations.
 
 The system of linear (algebraic) equations
 
-```
+```text
 2x + 3y +  z + 5w + 6u + 0v == 26,
  x + 2y + 3z + 4w + 5u + 2v == 28,
 3x + 2y + 5z +  w + 7u + 3v == 32,
@@ -16,7 +16,7 @@ The system of linear (algebraic) equations
 ```
 has solution x = 1, y = 2, z = 2, w = 2, u = 1, v = 2. So we have
 
-```
+```text
  ┌─ ─┐T ┌─           ─┐   ┌────┐T
  │ 1 │  │ 2 1 3 2 5 1 │   │ 26 │
  │ 2 │  │ 3 2 2 1 3 4 │   │ 28 │
@@ -31,80 +31,1090 @@ has solution x = 1, y = 2, z = 2, w = 2, u = 1, v = 2. So we have
 
 We solve an alternative problem in which R and C are given and M is solved for. We constrain the matrix M by giving values min_j and max_j such that the sum of values in row j has minimum value min_j and maximum value max_j.
 
-In general, there may be multiple solutions. To generate solutions, call `System.solve()` until it returns false. When it returns true, the solution is extracted with `System.solution(row, column)`.
+In general, there may be multiple solutions. To generate solutions, call `System.solve()` until it returns false. When it returns true, the solution is extracted with `System.solution(row, column)`, or with `System.solution_unchecked(row, column)`, which panics instead of returning an error.
 
 
-```rust
-
+```rust,no_run
+# use diophantine::{DiophantineError, DiophantineSystem};
+# fn main() -> Result<(), DiophantineError> {
 let mut system = DiophantineSystem::new(6, 6);
-system.insert_row(1, 10, 20); // 14 = actual sum of row
-system.insert_row(2, 11, 19); // 15 = actual sum of row
-system.insert_row(2, 15, 20); // 17 = actual sum of row
-system.insert_row(2, 15, 20); // 18 = actual sum of row
-system.insert_row(1, 30, 38); // 34 = actual sum of row
-system.insert_row(2, 12, 16); // 15 = actual sum of row
-system.insert_column(26);
-system.insert_column(28);
-system.insert_column(32);
-system.insert_column(25);
-system.insert_column(41);
-system.insert_column(26);
+system.insert_row(1, 10, 20)?; // 14 = actual sum of row
+system.insert_row(2, 11, 19)?; // 15 = actual sum of row
+system.insert_row(2, 15, 20)?; // 17 = actual sum of row
+system.insert_row(2, 15, 20)?; // 18 = actual sum of row
+system.insert_row(1, 30, 38)?; // 34 = actual sum of row
+system.insert_row(2, 12, 16)?; // 15 = actual sum of row
+system.insert_column(26)?;
+system.insert_column(28)?;
+system.insert_column(32)?;
+system.insert_column(25)?;
+system.insert_column(41)?;
+system.insert_column(26)?;
 
 while system.solve() {
   println!("\nSolution:");
   for row in 0..6 {
     for col in 0..6 {
-      print!("{}  ", system.solution(row, col));
+      print!("{}  ", system.solution(row, col)?);
     }
     println!();
   }
 }
-
+# Ok(())
+# }
 ```
 
 */
 
-use std::cmp::{max, min};
+use alloc::{boxed::Box, collections::BTreeSet, vec::Vec};
+use core::{
+  cmp::{max, min, Ordering, Reverse},
+  fmt::Debug,
+};
+
+use crate::{
+  merge::RowMerging,
+  row::Row,
+  solubility::{SolubilityTables, SolubilityVector},
+  solution::{self, Solution, Solutions},
+  spec::{RowSpec, SystemSpec},
+  stats::SolveStats,
+  ceiling_division,
+  floor_division,
+  DiophantineError,
+  DiophantineInt,
+  Soluble,
+  Select
+};
+
+/// Checks an invariant of the solver. With the `debug_checks` feature, a violation marks the system as failed and
+/// returns `$failure`, if given, from the enclosing function, for `try_solve()` to report; otherwise it panics with
+/// `$message`.
+macro_rules! invariant {
+  ($system:ident, $condition:expr, $message:expr $(, $failure:expr)?) => {
+    #[cfg(feature = "debug_checks")]
+    {
+      if !$condition {
+        $system.invariant_violated = true;
+        $system.failed             = true;
+        return $($failure)?;
+      }
+    }
+    #[cfg(not(feature = "debug_checks"))]
+    assert!($condition, $message);
+  };
+}
+
+/// Passed as `max_size` to `insert_row`, bounds the row sum only by the column total. This is the `u32` value of
+/// `DiophantineInt::UNBOUNDED`.
+pub const UNBOUNDED: u32 = <u32 as DiophantineInt>::UNBOUNDED;
+
+/// Which of the two searches solves a system, as reported by `DiophantineSystem::classify()`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SystemClass {
+  /// Solved by plain multiset selection.
+  Simple,
+  /// Solved with the help of solubility vectors.
+  Complex,
+}
+
+/// What a call to `DiophantineSystem::solve_outcome()` found.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SolveOutcome {
+  /// The next solution, which `solution()` now reads.
+  Solution,
+  /// No more solutions.
+  Exhausted,
+  /// The node budget ran out before the next solution was found.
+  Interrupted,
+}
 
-use crate::{row::Row, ceiling_division, floor_division, Soluble, Select};
+/// Why a system has no solutions, as found before the search and reported by
+/// `DiophantineSystem::explain_infeasibility()`. The sums are those of the rows and columns left once any fixed
+/// entries are taken out, saturated at the largest value of the integer type.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Infeasibility<T: DiophantineInt = u32> {
+  /// The fixed entries exceed their bounds, their rows' `max_size` or their columns, or a row other than the
+  /// extension row has a `max_size` of zero.
+  InconsistentConstraints,
+  /// The rows take more than the columns hold even at their minimum sums: `sum_of_min`, the sum of
+  /// `coeff * min_size` over the rows, exceeds `column_sum`.
+  MinProductsTooLarge { sum_of_min: T, column_sum: T },
+  /// The rows cannot make up the columns even at their maximum sums: `sum_of_max`, the sum of `coeff * max_size`
+  /// over the rows, falls short of `column_sum`.
+  MaxProductsTooSmall { sum_of_max: T, column_sum: T },
+  /// No combination of the rows can make up the column inserted `column`th on its own, whose value, less any fixed
+  /// entries, is `value`. Found with the solubility vectors, so only for complex systems.
+  InsolubleColumn { column: usize, value: T },
+}
 
-const UNBOUNDED: u32 = u32::MAX;
+/// The order in which `solve()` assigns the rows, as set by `DiophantineSystem::set_row_order()`. The order affects
+/// only the speed of the search and the order of the solutions, not the solutions themselves. Rows are always
+/// compared as they were inserted, and rows that compare equal are searched in insertion order.
+#[derive(Copy, Clone, Debug)]
+pub enum RowOrder<T: DiophantineInt = u32> {
+  /// Descending by coefficient, then ascending by `max_size`. This is the default.
+  DescCoeff,
+  /// Descending by `coeff * max_size`, the most a row can take from the columns, then as `DescCoeff`. An `UNBOUNDED`
+  /// row can take up to the column total.
+  DescMaxProduct,
+  /// Ascending by the given comparison: the row that compares least is assigned first.
+  Custom(fn(&RowSpec<T>, &RowSpec<T>) -> Ordering),
+}
 
-pub struct DiophantineSystem {
-  rows        : Vec<Row>,
-  columns     : Vec<u32>,
+/// A system of linear Diophantine equations over the integer type `T`, which defaults to `u32`. Cloning a system
+/// mid-search forks it: the clone continues with exactly the solutions the original has yet to produce.
+#[derive(Clone)]
+pub struct DiophantineSystem<T: DiophantineInt = u32> {
+  rows        : Vec<Row<T>>,
+  columns     : Vec<T>,   // residual column values, mutated in place during the search
+  original_columns : Vec<T>, // column values as inserted
   row_permute : Vec<u32>,
+  column_permute : Vec<u32>, // position of each inserted column in `columns`, empty unless sorting columns
 
-  column_sum        : u32,
-  max_column_value  : u32,
+  column_sum        : T,
+  max_column_value  : T,
   closed            : bool, // System is closed once we start solving
   complex           : bool,
-  failed            : bool  // Set when failure detected
+  failed            : bool, // Set when failure detected
+  row_merging       : bool, // Solve groups of identical rows together
+  column_sorting    : bool, // Search the columns in descending order of value
+  row_order         : RowOrder<T>,
+  coeff_descending  : bool, // The rows are sorted by descending coefficient, which viable() relies on
+  merging           : Option<Box<RowMerging<T>>>, // Search state when merging rows
+  stats             : SolveStats,
+  solution_limit    : Option<u64>, // solve() fails once this many solutions have been found
+  search_started    : bool, // Set by the first call to solve()
+  node_budget       : Option<u64>, // solve() gives up after visiting this many nodes without finding a solution
+  budget_start      : u64,  // stats.nodes_visited when the current call to solve() began
+  interrupted       : bool, // Set when the node budget runs out
+  solubility_memory : Option<usize>, // bytes the solubility vectors may take
+  too_large         : bool, // Set when the solubility vectors would exceed `solubility_memory`
+  allocation_failed : bool, // Set when the solubility vectors could not be allocated
+  last_row_shortcut : bool, // The last row takes what is left rather than searching for a selection
+  solution_delta    : bool, // solve() keeps the previous solution for solution_delta()
+  previous_solution : Vec<T>, // the solution before the current one as by solution_flat(), empty if none
+  extension_row     : Option<usize>, // insertion index of the only row allowed an empty sum
+  lex               : bool, // Solutions are produced in lexicographic order, set by solve_lex()
+  total_order       : Option<(T, Option<T>)>, // (current total, least greater total) of solve_by_total_ascending()
+  soluble_rows      : Vec<(u32, T)>, // (name, max_size) of the rows, in search order, the solubility vectors are for
+  infeasibility     : Option<Infeasibility<T>>, // why start() or precompute() found the system insoluble
+  invariant_violated : bool, // Set when an invariant check fails with the debug_checks feature
 }
 
 
-impl DiophantineSystem {
+impl DiophantineSystem<u32> {
 
   pub fn new(row_count: usize, col_count: usize) -> Self {
+    DiophantineSystem::with_capacity(row_count, col_count)
+  }
+
+  /// Builds an open system from its rows `(coeff, min_size, max_size)` and columns in one go, as if by calling
+  /// `insert_row` and `insert_column` in order. For other integer types, see `SystemSpec::to_system`.
+  pub fn from_vectors(rows: &[(u32, u32, u32)], columns: &[u32]) -> Result<Self, DiophantineError> {
+    let mut system = DiophantineSystem::new(rows.len(), columns.len());
+    for &(coeff, min_size, max_size) in rows.iter() {
+      system.insert_row(coeff, min_size, max_size)?;
+    }
+    for &column in columns.iter() {
+      system.insert_column(column)?;
+    }
+    Ok(system)
+  }
+
+  /// The first solution, as a matrix like `solution_matrix()`, of the system with rows `rows` for each of the column
+  /// vectors in `column_sets`, or `None` where that system has none: one pattern matched against many subjects. A
+  /// system is built once for each distinct number of columns and its columns are replaced for each set, and the sets
+  /// are solved in descending order of their largest column, so that the solubility vectors of a complex system, built
+  /// for the largest, are reused for the rest as by `with_new_columns()`. Fails if the rows or any column set would
+  /// fail to build with `from_vectors()`.
+  pub fn solve_batch(
+    rows        : &[(u32, u32, u32)],
+    column_sets : &[Vec<u32>],
+  ) -> Result<Vec<Option<Vec<Vec<u32>>>>, DiophantineError> {
+    let mut order: Vec<usize> = (0..column_sets.len()).collect();
+    order.sort_by_key(|&k| Reverse(column_sets[k].iter().max().copied().unwrap_or(0)));
+
+    let mut systems  : Vec<DiophantineSystem> = Vec::new(); // one for each number of columns
+    let mut solutions = vec![None; column_sets.len()];
+    for k in order {
+      let columns = &column_sets[k];
+      let system  = match systems.iter().position(|system| system.column_count() == columns.len()) {
+        Some(i) => {
+          let system = &mut systems[i];
+          system.reset();
+          for (j, &value) in columns.iter().enumerate() {
+            system.set_column(j, value)?;
+          }
+          system
+        }
+        None => {
+          systems.push(DiophantineSystem::from_vectors(rows, columns)?);
+          systems.last_mut().unwrap()
+        }
+      };
+      if system.solve() {
+        solutions[k] = Some(system.solution_matrix());
+      }
+    }
+    Ok(solutions)
+  }
+
+  /// Builds an open system from a system of linear equations $A \cdot x = b$ over the naturals, for problems that
+  /// are not stated in terms of $R$, $C$ and $M$.
+  ///
+  /// The system $R \cdot M = C$ is a set of $m$ equations, one per column: equation $j$ is
+  /// $\sum_i R_i M_{i,j} = C_j$. Each equation has its own $n$ unknowns, column $j$ of $M$, but every equation has
+  /// the same coefficients $R$, and the equations are tied together only by the bounds on the row sums
+  /// $\sum_j M_{i,j}$, which are the total of the $i$th unknown over all equations. So `a[j][i]` is the coefficient
+  /// of $M_{i,j}$ in equation `j`, `b[j]` is its right-hand side $C_j$, and `row_bounds[i]` gives the `min_size` and
+  /// `max_size` of row `i`, where `max_size` may be `UNBOUNDED`. For a single equation $a \cdot x = b$ with bounds
+  /// on each $x_i$, pass one row of `a`, one value of `b`, and the bounds of each $x_i$.
+  ///
+  /// Fails with `DiophantineError::InvalidMatrix` unless `a` has a row for each value of `b`, each with an entry for
+  /// each of `row_bounds`, and all rows of `a` are the same; a general integer matrix $A$ is not of this form. The
+  /// rows and columns are then checked as by `from_vectors`.
+  pub fn from_matrix(a: &[Vec<u32>], b: &[u32], row_bounds: &[(u32, u32)]) -> Result<Self, DiophantineError> {
+    if a.len() != b.len() || a.iter().any(|equation| equation.len() != row_bounds.len() || *equation != a[0]) {
+      return Err(DiophantineError::InvalidMatrix);
+    }
+    // Without equations the coefficients are immaterial.
+    let rows: Vec<(u32, u32, u32)> = row_bounds
+      .iter()
+      .enumerate()
+      .map(|(i, &(min_size, max_size))| (a.first().map_or(1, |coeffs| coeffs[i]), min_size, max_size))
+      .collect();
+    DiophantineSystem::from_vectors(&rows, b)
+  }
+
+  /// Same as `from_vectors`, but takes ownership of the vectors and fills out the system directly rather than through
+  /// `insert_row` and `insert_column`. For building many small systems, as in benchmarks.
+  pub fn from_raw(rows: Vec<(u32, u32, u32)>, columns: Vec<u32>) -> Result<Self, DiophantineError> {
+    let mut system = DiophantineSystem::new(0, 0);
+    system.rows = rows
+      .into_iter()
+      .enumerate()
+      .map(|(i, (coeff, min_size, max_size))| Self::new_row(i, coeff, min_size, max_size))
+      .collect::<Result<_, _>>()?;
+
+    let mut column_sum: u32 = 0;
+    for &value in columns.iter() {
+      column_sum = column_sum.checked_add(value).ok_or(DiophantineError::Overflow)?;
+    }
+    system.column_sum       = column_sum;
+    system.max_column_value = columns.iter().fold(0, |m, &column| max(m, column));
+    system.original_columns = columns.clone();
+    system.columns          = columns;
+    Ok(system)
+  }
+
+}
+
+
+impl<T: DiophantineInt> DiophantineSystem<T> {
+
+  /// Creates an empty system over the integer type `T` with room for the given number of rows and columns. For the
+  /// default `u32` systems this is the same as `new`.
+  pub fn with_capacity(row_count: usize, col_count: usize) -> Self {
     DiophantineSystem {
       rows              : Vec::with_capacity(row_count),
       columns           : Vec::with_capacity(col_count),
+      original_columns  : Vec::with_capacity(col_count),
       row_permute       : Vec::new(),
-      column_sum        : 0,
-      max_column_value  : 0,
+      column_permute    : Vec::new(),
+      column_sum        : T::ZERO,
+      max_column_value  : T::ZERO,
       closed            : false,
       complex           : false,
       failed            : false,
+      row_merging       : false,
+      column_sorting    : false,
+      row_order         : RowOrder::DescCoeff,
+      coeff_descending  : false,
+      merging           : None,
+      stats             : SolveStats::default(),
+      solution_limit    : None,
+      search_started    : false,
+      node_budget       : None,
+      budget_start      : 0,
+      interrupted       : false,
+      solubility_memory : None,
+      too_large         : false,
+      allocation_failed : false,
+      last_row_shortcut : true,
+      solution_delta    : false,
+      previous_solution : Vec::new(),
+      extension_row     : None,
+      lex               : false,
+      total_order       : None,
+      soluble_rows      : Vec::new(),
+      infeasibility     : None,
+      invariant_violated : false,
+    }
+  }
+
+  /// The value of $M_{r,c}$ in the current solution, where `r` indexes rows in insertion order. Fails if there is no
+  /// current solution or the indices are out of range.
+  #[inline(always)]
+  pub fn solution(&self, r: usize, c: usize) -> Result<T, DiophantineError> {
+    if !self.closed {
+      return Err(DiophantineError::NoSolutionYet);
+    }
+    if self.failed {
+      return Err(DiophantineError::Exhausted);
+    }
+    if r >= self.rows.len() || c >= self.columns.len() {
+      return Err(self.index_out_of_range(r, c));
+    }
+
+    Ok(self.solution_unchecked(r, c))
+  }
+
+  /// The part of $M_{r,c}$ in the current solution that the search was forced to assign rather than chose: the least
+  /// row `r` can take from column `c` so that the rows searched after it can make up the rest, any value fixed with
+  /// `fix_cell`, and all of the entry if row `r` is searched last, since that row takes whatever is left. Only complex
+  /// systems force more than fixed values and the last row, and with row merging nothing is reported as forced. Fails
+  /// as `solution()` does.
+  pub fn solution_base(&self, r: usize, c: usize) -> Result<T, DiophantineError> {
+    let entry = self.solution(r, c)?;
+    let i     = self.row_permute[r] as usize;
+    if self.merging.is_none() && i + 1 == self.rows.len() {
+      return Ok(entry);
+    }
+    let row = &self.rows[i];
+    let j   = self.column_index(c);
+    Ok(row.selection[j].base + row.fixed_value(j).unwrap_or(T::ZERO))
+  }
+
+  /// The part of $M_{r,c}$ in the current solution chosen by the search, so that
+  /// `solution_base(r, c)? + solution_extra(r, c)? == solution(r, c)?`. Fails as `solution()` does.
+  pub fn solution_extra(&self, r: usize, c: usize) -> Result<T, DiophantineError> {
+    Ok(self.solution(r, c)? - self.solution_base(r, c)?)
+  }
+
+  /// The sum of the forced parts of the entries of row `i` in the current solution, as by `solution_base()`, where `i`
+  /// indexes rows in insertion order. `forced_total(i) + free_total(i)` is the row's sum. Panics if there is no current
+  /// solution or `i` is out of range.
+  pub fn forced_total(&self, i: usize) -> T {
+    assert!(self.closed, "solve() not called");
+    assert!(!self.failed, "non-existent soluiton");
+    assert!(i < self.rows.len(), "row index out of range");
+
+    (0..self.columns.len()).fold(T::ZERO, |total, c| total + self.solution_base(i, c).unwrap())
+  }
+
+  /// The sum of the parts of the entries of row `i` in the current solution chosen by the search, as by
+  /// `solution_extra()`, where `i` indexes rows in insertion order. Panics as `forced_total()` does.
+  pub fn free_total(&self, i: usize) -> T {
+    let sum = self.solution_row(i).iter().fold(T::ZERO, |sum, &entry| sum + entry);
+    sum - self.forced_total(i)
+  }
+
+  /// The error for the entry at row `r` and column `c`, which does not exist.
+  fn index_out_of_range(&self, r: usize, c: usize) -> DiophantineError {
+    DiophantineError::IndexOutOfRange {
+      row       : r,
+      col       : c,
+      row_count : self.rows.len(),
+      col_count : self.columns.len(),
+    }
+  }
+
+  /// Same as `solution()`, but panics instead of returning an error. For use in hot loops.
+  #[inline(always)]
+  pub fn solution_unchecked(&self, r: usize, c: usize) -> T {
+    assert!(self.closed, "solve() not called");
+    assert!(!self.failed, "non-existent soluiton");
+
+    self.rows[self.row_permute[r] as usize].entry(self.column_index(c))
+  }
+
+  /// The value of $M_{r,c}$ in the current solution, where, unlike `solution()`, `sorted_row` indexes rows in search
+  /// order: the row at position `sorted_row` is the one inserted `i`th for which `row_permutation()[i] == sorted_row`.
+  /// Search order is the one `row_leave_bounds()` uses, and in which the `solubility()` vector of a row accounts for
+  /// the rows after it, so use this to correlate a solution with those; use `solution()` to read the rows as inserted.
+  /// `col` indexes columns in insertion order either way.
+  /// Panics if there is no current solution or the indices are out of range.
+  pub fn solution_sorted(&self, sorted_row: usize, col: usize) -> T {
+    assert!(self.closed, "solve() not called");
+    assert!(!self.failed, "non-existent soluiton");
+    assert!(sorted_row < self.rows.len() && col < self.columns.len(), "{}", self.index_out_of_range(sorted_row, col));
+
+    self.rows[sorted_row].entry(self.column_index(col))
+  }
+
+  /// Returns an iterator over the remaining solutions of the system. The iterator drives `solve()`, yielding an owned
+  /// snapshot of $M$ for each solution found, and ends when `solve()` returns `false`.
+  pub fn solutions(&mut self) -> Solutions<'_, T> {
+    Solutions::new(self)
+  }
+
+  /// Returns an iterator over the remaining solutions of the system, yielding the nonzero entries of each as
+  /// `(row, column, value)` triples, as by `solution_nonzero()`. For sparse solutions of systems with many columns,
+  /// this avoids the cost of copying the dense matrix.
+  pub fn solution_assignments(&mut self) -> impl Iterator<Item = Vec<(usize, usize, T)>> + '_ {
+    core::iter::from_fn(move || if self.solve() { Some(self.solution_nonzero()) } else { None })
+  }
+
+  /// Returns an iterator over the remaining solutions of the system, as matrices like `solution_matrix()`, skipping
+  /// any solution that is a permutation of one already yielded. Only interchangeable rows (see
+  /// `count_solutions_canonical()`) can be permuted into one another, and the search yields every permutation of them
+  /// as a separate solution; this yields the first one found of each. Every solution yielded is kept, sorted within
+  /// each group of interchangeable rows, to recognize the permutations that follow, so the memory taken grows with the
+  /// number of distinct solutions. `count_solutions_canonical()` counts them without that cost.
+  pub fn solve_deduplicated(&mut self) -> impl Iterator<Item = Vec<Vec<T>>> + '_ {
+    // Each group of interchangeable rows with more than one member, in insertion order.
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    for i in 0..self.rows.len() {
+      match groups.iter_mut().find(|group| self.interchangeable(group[0], i)) {
+        Some(group) => group.push(i),
+        None        => groups.push(vec![i]),
+      }
+    }
+    groups.retain(|group| group.len() > 1);
+
+    let mut seen = BTreeSet::new();
+    core::iter::from_fn(move || {
+      while !self.failed && self.solve() {
+        let matrix  = self.solution_matrix();
+        let mut key = matrix.clone();
+        for group in groups.iter() {
+          let mut rows: Vec<Vec<T>> = group.iter().map(|&i| key[i].clone()).collect();
+          rows.sort();
+          for (&i, row) in group.iter().zip(rows) {
+            key[i] = row;
+          }
+        }
+        if seen.insert(key) {
+          return Some(matrix);
+        }
+      }
+      None
+    })
+  }
+
+  /// Advances to the next solution and takes a snapshot of it, or returns `None` if there are no more solutions.
+  pub(crate) fn next_solution(&mut self) -> Option<Solution<T>> {
+    // Once the search is exhausted `solve()` must not be called again.
+    if self.failed || !self.solve() {
+      return None;
+    }
+
+    Some(Solution::new(self.row_count(), self.column_count(), self.solution_flat()))
+  }
+
+  /// Exhausts the search, returning the number of solutions found. Only solutions not yet produced by earlier calls to
+  /// `solve()` are counted. Afterward the system is exhausted, so `solution()` is invalid. No solution is copied.
+  pub fn count_solutions(&mut self) -> u64 {
+    let mut count = 0;
+    self.for_each_solution(|_| {
+      count += 1;
+      true
+    });
+    count
+  }
+
+  /// Exhausts the search like `count_solutions()`, but counts only canonical solutions: those in which the rows of
+  /// each group of interchangeable rows are in lexicographically nondecreasing order, in insertion order. Rows are
+  /// interchangeable when they have the same coefficient and bounds, including bounds on and fixed values of their
+  /// entries, either both or neither is a zero row of `solve_with_zero_rows()`, and neither is the extension row.
+  /// Permuting interchangeable rows turns a solution into another solution, and exactly one of each set of solutions
+  /// related this way is canonical.
+  pub fn count_solutions_canonical(&mut self) -> u64 {
+    let column_count = self.original_columns.len();
+    // The nearest row before each row that is interchangeable with it.
+    let previous: Vec<Option<usize>> = (0..self.rows.len())
+        .map(|i| (0..i).rev().find(|&j| self.interchangeable(i, j)))
+        .collect();
+
+    let mut count = 0;
+    self.for_each_solution(|system| {
+      let canonical = previous.iter().enumerate().all(|(i, &j)| match j {
+        Some(j) => {
+          let row = |r: usize| (0..column_count).map(move |c| system.solution_unchecked(r, c));
+          row(j).le(row(i))
+        }
+        None => true,
+      });
+      if canonical {
+        count += 1;
+      }
+      true
+    });
+    count
+  }
+
+  /// Whether the rows inserted `i`th and `j`th can be exchanged in any solution to give another solution.
+  fn interchangeable(&self, i: usize, j: usize) -> bool {
+    let (a, b) = (&self.rows[self.row_index(i)], &self.rows[self.row_index(j)]);
+    a.coeff == b.coeff
+        && a.inserted_min_size == b.inserted_min_size
+        && a.inserted_max_size == b.inserted_max_size
+        && self.extension_row != Some(i)
+        && self.extension_row != Some(j)
+        && (0..self.original_columns.len())
+            .all(|c| a.cell_max(c) == b.cell_max(c) && a.fixed_value(c) == b.fixed_value(c))
+        && a.cell_count_max == b.cell_count_max
+        && a.zero == b.zero
+  }
+
+  /// The first solution of the system as it was built, as a matrix like `solution_matrix()`, or `None` if there is
+  /// none. The search runs on a fresh copy, so `self` is left untouched, however far its own search has progressed.
+  pub fn first_solution(&self) -> Option<Vec<Vec<T>>> {
+    let mut system = self.clone();
+    system.reset();
+    if system.solve() {
+      Some(system.solution_matrix())
+    } else {
+      None
+    }
+  }
+
+  /// Drives `solve()` to find up to `max` more solutions and writes them to `buf`, each in the row-major layout of
+  /// `solution_flat()`, returning how many were written. `buf` is left holding exactly those solutions, and the
+  /// vectors already in it are overwritten in place, so a buffer passed to successive calls allocates only while it
+  /// grows.
+  pub fn collect_solutions_into(&mut self, buf: &mut Vec<Vec<T>>, max: usize) -> usize {
+    let mut written = 0;
+    while written < max && !self.failed && self.solve() {
+      if written == buf.len() {
+        buf.push(Vec::with_capacity(self.rows.len() * self.columns.len()));
+      }
+      buf[written].clear();
+      buf[written].extend(self.iter_solution_cells());
+      written += 1;
+    }
+    buf.truncate(written);
+    written
+  }
+
+  /// Whether the system has any solution at all, however far the search has progressed. Searches for one solution and
+  /// then puts the system back as it was: an open system is `reset()`, keeping the solubility vectors for the search
+  /// that follows, while a search in progress is left untouched and the question is answered by a copy. Returns false
+  /// if the node budget or the solubility memory limit runs out first.
+  pub fn is_feasible(&mut self) -> bool {
+    self.count_solutions_up_to(1) == 1
+  }
+
+  /// Whether the system has exactly one solution, however far the search has progressed. Stops at the second
+  /// solution, and puts the system back as it was, as `is_feasible()` does. Returns false if the node budget or the
+  /// solubility memory limit runs out first.
+  pub fn is_unique(&mut self) -> bool {
+    self.count_solutions_up_to(2) == 1
+  }
+
+  /// Counts the solutions of the system as it was built, stopping at `limit`, and then puts the system back as it
+  /// was: an open system is `reset()`, while a search in progress is left untouched and the count is made on a copy.
+  fn count_solutions_up_to(&mut self, limit: u64) -> u64 {
+    if self.search_started {
+      let mut system = self.clone();
+      system.reset();
+      return system.count_solutions_up_to(limit);
+    }
+    let mut count = 0;
+    while count < limit && self.solve() {
+      count += 1;
+    }
+    self.reset();
+    count
+  }
+
+  /// Drives `solve()`, calling `f` on the system after each solution is found so that it can read the solution in
+  /// place with `solution()`. Stops early, leaving the current solution in place, if `f` returns `false`. This is the
+  /// allocation-free counterpart of `solutions()`.
+  pub fn for_each_solution<F: FnMut(&DiophantineSystem<T>) -> bool>(&mut self, mut f: F) {
+    // Once the search is exhausted `solve()` must not be called again.
+    while !self.failed && self.solve() {
+      if !f(self) {
+        break;
+      }
+    }
+  }
+
+  /// Drives `solve()` and collects the matrices, as by `solution_matrix()`, of the solutions for which `pred` returns
+  /// `true`, stopping once `max` have been found. The last solution examined is left in place.
+  pub fn find_solutions<F>(&mut self, max: usize, mut pred: F) -> Vec<Vec<Vec<T>>>
+    where F: FnMut(&DiophantineSystem<T>) -> bool
+  {
+    let mut found = Vec::new();
+    if max == 0 {
+      return found;
+    }
+    self.for_each_solution(|system| {
+      if pred(system) {
+        found.push(system.solution_matrix());
+      }
+      found.len() < max
+    });
+    found
+  }
+
+  /// Checks the current solution from scratch: $R \cdot M$ must equal the inserted $C$, and every row sum must lie in
+  /// `[min_size, max_size]`. Returns false if it does not, or if there is no current solution.
+  pub fn verify_current_solution(&self) -> bool {
+    if !self.closed || self.failed {
+      return false;
+    }
+
+    for row in self.rows.iter() {
+      let sum = (0..self.columns.len()).fold(T::ZERO, |sum, c| sum + row.entry(c));
+      // The row bounds apply to the entries that are not fixed.
+      let sum = sum - row.fixed_sum;
+      if sum < row.min_size || (!row.unbounded && sum > row.max_size) {
+        return false;
+      }
     }
+
+    self.reconstructed_columns() == self.original_columns
+  }
+
+  /// The columns $R \cdot M$ of the current solution $M$, in insertion order, computed from scratch. For a valid
+  /// solution they equal the inserted columns. Panics if there is no current solution.
+  pub fn reconstructed_columns(&self) -> Vec<T> {
+    assert!(self.closed, "solve() not called");
+    assert!(!self.failed, "non-existent soluiton");
+
+    let mut column_totals = vec![T::ZERO; self.columns.len()];
+    for row in self.rows.iter() {
+      for (c, total) in column_totals.iter_mut().enumerate() {
+        *total += row.entry(c) * row.coeff;
+      }
+    }
+    (0..self.original_columns.len()).map(|j| column_totals[self.column_index(j)]).collect()
+  }
+
+  /// Returns the system to its state before the first call to `solve()`, so that the search can be run again from
+  /// scratch. A fresh `solve()` loop reproduces the same sequence of solutions.
+  pub fn reset(&mut self) {
+    self.columns.copy_from_slice(&self.original_columns);
+    self.column_sum       = T::ZERO;
+    self.max_column_value = T::ZERO;
+    for &column in self.original_columns.iter() {
+      self.column_sum += column;
+      self.max_column_value = max(self.max_column_value, column);
+    }
+
+    // Restore insertion order and undo the modifications made by `precompute()`.
+    self.rows.sort_by_key(|row| row.name);
+    for row in self.rows.iter_mut() {
+      row.min_size         = row.inserted_min_size;
+      row.max_size         = row.inserted_max_size;
+      row.unbounded        = row.inserted_max_size == T::UNBOUNDED;
+      row.current_size     = T::ZERO;
+      row.current_max_size = T::ZERO;
+      row.selection.clear();
+      // The solubility vectors are kept for `precompute()` to reuse if they still fit.
+      if !self.column_permute.is_empty() {
+        row.permute_columns(&self.column_permute, true);
+      }
+    }
+    self.row_permute.clear();
+    self.column_permute.clear();
+
+    self.closed  = false;
+    self.complex = false;
+    self.failed  = false;
+    self.merging = None;
+    self.stats   = SolveStats::default();
+    self.search_started = false;
+    self.interrupted    = false;
+    self.too_large      = false;
+    self.allocation_failed = false;
+    self.previous_solution.clear();
+    self.lex            = false;
+    self.total_order    = None;
+    self.infeasibility = None;
+    self.invariant_violated = false;
+  }
+
+  /// Releases the spare capacity of the system's vectors, including the selections and solubility vectors of the
+  /// rows, for a system kept around long after it was solved. The contents are untouched, so the current solution and
+  /// the search state survive, and the solubility vectors are still reused by a search after `reset()`. A later search
+  /// may have to grow the vectors again.
+  pub fn shrink_to_fit(&mut self) {
+    for row in self.rows.iter_mut() {
+      row.cell_max.shrink_to_fit();
+      row.fixed.shrink_to_fit();
+      row.selection.shrink_to_fit();
+      row.soluble.shrink_to_fit();
+    }
+    self.rows.shrink_to_fit();
+    self.columns.shrink_to_fit();
+    self.original_columns.shrink_to_fit();
+    self.row_permute.shrink_to_fit();
+    self.column_permute.shrink_to_fit();
+    self.previous_solution.shrink_to_fit();
+    self.soluble_rows.shrink_to_fit();
+    if let Some(merging) = self.merging.as_mut() {
+      merging.shrink_to_fit();
+    }
+  }
+
+  /// The current solution as a matrix, with `matrix[r][c] == solution(r, c)`. Rows are in insertion order.
+  pub fn solution_matrix(&self) -> Vec<Vec<T>> {
+    assert!(self.closed, "solve() not called");
+    assert!(!self.failed, "non-existent soluiton");
+
+    self
+      .row_permute
+      .iter()
+      .map(|&i| {
+        let row = &self.rows[i as usize];
+        (0..self.columns.len()).map(|c| row.entry(self.column_index(c))).collect()
+      })
+      .collect()
+  }
+
+  /// Row `i` of the current solution, with `row[c] == solution(i, c)`, where `i` indexes rows in insertion order.
+  pub fn solution_row(&self, i: usize) -> Vec<T> {
+    assert!(self.closed, "solve() not called");
+    assert!(!self.failed, "non-existent soluiton");
+
+    let row = &self.rows[self.row_permute[i] as usize];
+    (0..self.columns.len()).map(|c| row.entry(self.column_index(c))).collect()
+  }
+
+  /// Column `j` of the current solution, with `column[r] == solution(r, j)`, where `r` indexes rows in insertion
+  /// order.
+  pub fn solution_column(&self, j: usize) -> Vec<T> {
+    assert!(self.closed, "solve() not called");
+    assert!(!self.failed, "non-existent soluiton");
+    assert!(j < self.columns.len(), "column index out of range");
+
+    let c = self.column_index(j);
+    self.row_permute.iter().map(|&i| self.rows[i as usize].entry(c)).collect()
+  }
+
+  /// The current solution flattened in row-major order, so that `flat[r * column_count() + c] == solution(r, c)`.
+  /// Rows are in insertion order. This is the layout of a standard-order dense matrix, so it converts directly to,
+  /// say, an `ndarray` array without copying:
+  ///
+  /// ```ignore
+  /// let m = ndarray::Array2::from_shape_vec((system.row_count(), system.column_count()), system.solution_flat())?;
+  /// ```
+  pub fn solution_flat(&self) -> Vec<T> {
+    let mut cells = Vec::with_capacity(self.row_count() * self.column_count());
+    cells.extend(self.iter_solution_cells());
+    cells
+  }
+
+  /// A snapshot of the current solution. Snapshots compare and hash by their matrix, for collecting solutions into a
+  /// `HashSet`.
+  pub fn current_solution(&self) -> Solution<T> {
+    Solution::new(self.row_count(), self.column_count(), self.solution_flat())
+  }
+
+  /// A deterministic 64-bit hash of the current solution matrix in insertion order, for telling solutions apart
+  /// across systems. It depends only on the dimensions and entries of the matrix, not on the order of the search, so
+  /// the same assignment found by differently ordered systems has the same key. Equal to `Solution::key` of a
+  /// snapshot of the solution. Panics if there is no current solution.
+  pub fn solution_key(&self) -> u64 {
+    solution::solution_key(self.row_count(), self.column_count(), self.iter_solution_cells())
+  }
+
+  /// The entries of the current solution in the order of `solution_flat()`, without allocating. Panics if there is no
+  /// current solution.
+  pub fn iter_solution_cells(&self) -> impl Iterator<Item = T> + '_ {
+    assert!(self.closed, "solve() not called");
+    assert!(!self.failed, "non-existent soluiton");
+
+    self.row_permute.iter().flat_map(move |&i| {
+      let row = &self.rows[i as usize];
+      (0..self.columns.len()).map(move |c| row.entry(self.column_index(c)))
+    })
+  }
+
+  /// The entries of the current solution that differ from the previous solution, as `(row, column, change)` triples
+  /// in row-major order, where `change` is the current entry minus the previous one. For the first solution, and the
+  /// first after `reset()`, the previous solution is taken to be zero, so every nonzero entry is reported. Requires
+  /// `set_solution_delta(true)`. Panics if there is no current solution, if delta tracking is off, or if a change does
+  /// not fit in an `i64`.
+  pub fn solution_delta(&self) -> Vec<(usize, usize, i64)> {
+    assert!(self.solution_delta, "solution delta tracking not enabled");
+    let column_count = self.columns.len();
+    self
+      .iter_solution_cells()
+      .enumerate()
+      .filter_map(|(k, entry)| {
+        let previous = self.previous_solution.get(k).copied().unwrap_or(T::ZERO);
+        let change   = if entry >= previous {
+          (entry - previous).to_i64()
+        } else {
+          (previous - entry).to_i64().map(|change| -change)
+        };
+        match change.expect("solution delta out of range for i64") {
+          0      => None,
+          change => Some((k / column_count, k % column_count, change)),
+        }
+      })
+      .collect()
+  }
+
+  /// A deterministic 64-bit hash of the system as it was built that does not depend on the order in which rows and
+  /// columns were inserted, for caching solutions of subproblems that recur. Systems that differ only by the order of
+  /// their rows and columns, with their bounds on and fixed values of entries, their extension row and their zero rows
+  /// carried along, have the same solutions up to the same reordering and get the same fingerprint. As with any hash,
+  /// equal fingerprints do not guarantee equivalent systems, so compare the systems before reusing a cached result.
+  pub fn system_fingerprint(&self) -> u64 {
+    let spec = self.spec();
+    let word = |value: T| value.to_usize() as u64;
+
+    // Each row with its bounds and fixed values, identified by the values of their columns rather than the indices.
+    let mut rows: Vec<Vec<u64>> = spec.rows
+      .iter()
+      .enumerate()
+      .map(|(i, row)| {
+        let mut entries: Vec<(u64, u64, u64)> = spec.cell_max
+          .iter()
+          .map(|&(r, c, max)| (r, c, 0, max))
+          .chain(spec.fixed.iter().map(|&(r, c, value)| (r, c, 1, value)))
+          .filter(|&(r, ..)| r == i)
+          .map(|(_, c, kind, value)| (word(spec.columns[c]), kind, word(value)))
+          .collect();
+        entries.sort_unstable();
+
+        let extension = spec.extension_row == Some(i);
+        let count_max = spec.cell_count_max.iter().find(|&&(r, _)| r == i).map_or(u64::MAX, |&(_, max)| max as u64);
+        let zero      = spec.zero_rows.contains(&i);
+        let mut key   = vec![word(row.coeff), word(row.min_size), word(row.max_size), extension as u64, count_max];
+        key.push(zero as u64);
+        key.extend(entries.iter().flat_map(|&(column, kind, value)| vec![column, kind, value]));
+        key
+      })
+      .collect();
+    rows.sort_unstable();
+    let mut columns: Vec<u64> = spec.columns.iter().map(|&column| word(column)).collect();
+    columns.sort_unstable();
+
+    let rows = rows.iter().flat_map(|key| Some(key.len() as u64).into_iter().chain(key.iter().cloned()));
+    let words = Some(spec.rows.len() as u64).into_iter().chain(rows).chain(Some(columns.len() as u64)).chain(columns);
+    solution::fnv1a(words)
+  }
+
+  /// Describes the system as it was built, in insertion order, regardless of how far the search has progressed.
+  /// `spec().to_system()` is an open system with the same solutions as this one.
+  pub fn spec(&self) -> SystemSpec<T> {
+    let mut rows = vec![
+      RowSpec { coeff: T::ZERO, min_size: T::ZERO, max_size: T::ZERO };
+      self.rows.len()
+    ];
+    let mut cell_max       = Vec::new();
+    let mut fixed          = Vec::new();
+    let mut cell_count_max = Vec::new();
+    let mut zero_rows      = Vec::new();
+    for row in self.rows.iter() {
+      if let Some(max) = row.cell_count_max {
+        cell_count_max.push((row.name as usize, max));
+      }
+      if row.zero {
+        zero_rows.push(row.name as usize);
+      }
+      rows[row.name as usize] = RowSpec {
+        coeff    : row.coeff,
+        min_size : row.inserted_min_size,
+        max_size : row.inserted_max_size,
+      };
+      for c in 0..self.original_columns.len() {
+        let j = self.column_index(c);
+        match row.cell_max.get(j) {
+          Some(&max) if max != T::UNBOUNDED => cell_max.push((row.name as usize, c, max)),
+          _                                 => {}
+        }
+        if let Some(&Some(value)) = row.fixed.get(j) {
+          fixed.push((row.name as usize, c, value));
+        }
+      }
+    }
+
+    cell_max.sort();
+    fixed.sort();
+    cell_count_max.sort();
+    zero_rows.sort();
+
+    SystemSpec {
+      rows,
+      columns       : self.original_columns.clone(),
+      extension_row : self.extension_row,
+      cell_max,
+      fixed,
+      cell_count_max,
+      zero_rows,
+    }
+  }
+
+  /// The solubility vector of the row inserted `i`th, indexed by column value from 0 to `max_column_value()`: entry
+  /// `v` gives the least and greatest amount $K$ the row can take from a column of value `v` so that the rest,
+  /// $v - K \cdot R_i$, can still be made up by the rows after it in search order (see `row_permutation()`), or is
+  /// `INSOLUBLE` if there is no such $K$. Available once the first call to `solve()` (or `classify()`) has prepared a
+  /// complex system; `None` for simple systems, which have no solubility vectors, and with row merging. The vector is
+  /// a copy, since with the `compact_solubility` feature it is not stored as `Soluble` values.
+  pub fn solubility(&self, i: usize) -> Option<Vec<Soluble<T>>> {
+    if !self.closed || !self.complex || self.merging.is_some() || self.soluble_rows != self.soluble_rows() {
+      return None;
+    }
+    let soluble = &self.rows[self.row_index(i)].soluble;
+    if soluble.len() != self.max_column_value.to_usize() + 1 {
+      return None;
+    }
+    Some(soluble.to_vec())
+  }
+
+  /// The least and greatest total, $\sum_j R_k \cdot M_{k,j}$ over the rows $k$ after it, that the row at position `i`
+  /// in search order (see `row_permutation()`) must leave for the rest: the sums of the minimum and maximum products of
+  /// those rows. The search prunes any selection for the row that leaves a column total outside this range, so the
+  /// bounds tighten toward the last row, for which both are zero. Available once the first call to `solve()` (or
+  /// `classify()`) has sorted the rows; `None` before that, with row merging, or if `i` is out of range.
+  pub fn row_leave_bounds(&self, i: usize) -> Option<(T, T)> {
+    if !self.closed || self.merging.is_some() || self.row_permute.is_empty() {
+      return None;
+    }
+    self.rows.get(i).map(|row| (row.min_leave, row.max_leave))
+  }
+
+  /// Whether `value` can be made up by the rows at positions `from_sorted_row..` in search order (see
+  /// `row_permutation()`), as a natural number linear combination of their coefficients within their `max_size`: the
+  /// question the search asks of what each row leaves of a column. In a simple system the last row has coefficient 1
+  /// and a `max_size` of at least the largest column, so it makes up any value alone; in a complex system the answer
+  /// is read from the solubility vector of the row at `from_sorted_row`. For `from_sorted_row` equal to the number of
+  /// rows, only zero can be made up. Panics if `value` exceeds `max_column_value()`, if `from_sorted_row` is out of
+  /// range, or unless the first call to `solve()` (or `classify()`) has prepared the search, which it does not with
+  /// row merging or for a complex system found insoluble before its solubility vectors were built.
+  pub fn is_representable(&self, value: T, from_sorted_row: usize) -> bool {
+    assert!(self.closed && self.merging.is_none() && !self.row_permute.is_empty(), "search not prepared");
+    assert!(value <= self.max_column_value, "value exceeds the largest column");
+    assert!(from_sorted_row <= self.rows.len(), "row index out of range");
+
+    if from_sorted_row == self.rows.len() {
+      return value == T::ZERO;
+    }
+    if !self.complex {
+      return true;
+    }
+    let soluble = &self.rows[from_sorted_row].soluble;
+    assert!(
+      self.soluble_rows == self.soluble_rows() && soluble.len() > value.to_usize(),
+      "solubility vectors not built"
+    );
+    soluble.is_soluble(value.to_usize())
+  }
+
+  /// A copy of the solubility vectors of the system, to be attached to other systems with the same rows by
+  /// `with_solubility()`. Available when `solubility()` is.
+  pub fn extract_solubility(&self) -> Option<SolubilityTables<T>> {
+    if !self.closed || !self.complex || self.merging.is_some() || self.soluble_rows != self.soluble_rows() {
+      return None;
+    }
+    let length = self.max_column_value.to_usize() + 1;
+    if self.rows.iter().any(|row| row.soluble.len() != length) {
+      return None;
+    }
+    Some(SolubilityTables {
+      rows    : self.soluble_rows
+          .iter()
+          .zip(self.rows.iter())
+          .map(|(&(name, max_size), row)| (name, row.coeff, max_size))
+          .collect(),
+      vectors : self.rows.iter().map(|row| row.soluble.clone()).collect(),
+    })
+  }
+
+  /// Maps insertion order to search order: `row_permutation()[i]` is the position, in the order in which the search
+  /// assigns rows, of the row inserted `i`th. The search order is given by `set_row_order()`, by default descending by
+  /// coefficient and then ascending by `max_size`, or insertion order for `solve_lex()`. The slice is empty until the
+  /// first call to `solve()` (or `classify()`) has sorted the rows, and with row merging it is the identity.
+  pub fn row_permutation(&self) -> &[u32] {
+    &self.row_permute
+  }
+
+  /// The residual column values of the search: what is left of each column once the rows before the last have taken
+  /// their current selections, which the last row then takes. Before the search starts, and with row merging, these
+  /// are the inserted columns. The columns are in the order in which they are stored for the search, which is
+  /// insertion order unless column sorting is enabled (see `column_permutation()`).
+  pub fn working_columns(&self) -> &[T] {
+    &self.columns
+  }
+
+  /// Maps insertion order to the order in which the columns are stored for the search: `column_permutation()[j]` is
+  /// the position of the column inserted `j`th. The slice is empty unless `set_column_sorting(true)` was called and
+  /// the first call to `solve()` (or `classify()`) has sorted the columns. All methods taking or returning a column
+  /// index use insertion order regardless.
+  pub fn column_permutation(&self) -> &[u32] {
+    &self.column_permute
+  }
+
+  /// Position in `columns` of the column inserted `j`th.
+  #[inline(always)]
+  fn column_index(&self, j: usize) -> usize {
+    if self.column_permute.is_empty() {
+      j
+    } else {
+      self.column_permute[j] as usize
+    }
+  }
+
+  /// Position in `rows` of the row inserted `i`th. Rows stay in insertion order until `precompute()` sorts them and
+  /// fills out `row_permute`.
+  #[inline(always)]
+  fn row_index(&self, i: usize) -> usize {
+    if self.row_permute.is_empty() {
+      i
+    } else {
+      self.row_permute[i] as usize
+    }
+  }
+
+  /// The coefficient of the row inserted `i`th.
+  pub fn row_coeff(&self, i: usize) -> T {
+    self.rows[self.row_index(i)].coeff
+  }
+
+  /// The minimum sum of the row inserted `i`th, as it was inserted.
+  pub fn row_min_size(&self, i: usize) -> T {
+    self.rows[self.row_index(i)].inserted_min_size
+  }
+
+  /// The maximum sum of the row inserted `i`th, as it was inserted (possibly `UNBOUNDED`).
+  pub fn row_max_size(&self, i: usize) -> T {
+    self.rows[self.row_index(i)].inserted_max_size
+  }
+
+  /// The value of the column inserted `j`th, as it was inserted.
+  pub fn column_value(&self, j: usize) -> T {
+    self.original_columns[j]
+  }
+
+  /// The sum of the column values, available as soon as the columns are inserted. Once solving has started with fixed
+  /// entries (see `fix_cell`), this is the sum of what is left of the columns after the fixed entries are taken out.
+  #[inline(always)]
+  pub fn column_sum(&self) -> T {
+    self.column_sum
   }
 
+  /// The largest column value, available as soon as the columns are inserted. Once solving has started with fixed
+  /// entries (see `fix_cell`), this is the largest of what is left of the columns after the fixed entries are taken
+  /// out.
   #[inline(always)]
-  pub fn solution(&self, r: usize, c: usize) -> u32 {
+  pub fn max_column_value(&self) -> T {
+    self.max_column_value
+  }
+
+  /// The nonzero entries of the current solution as `(row, column, value)` triples in row-major order, with rows in
+  /// insertion order. Each triple assigns `value` copies of the constant of `column` to the variable of `row`.
+  pub fn solution_nonzero(&self) -> Vec<(usize, usize, T)> {
     assert!(self.closed, "solve() not called");
     assert!(!self.failed, "non-existent soluiton");
 
-    let s = &self.rows[self.row_permute[r] as usize].selection[c as usize];
-    return s.base + s.extra;
+    let mut entries = Vec::new();
+    for (r, &i) in self.row_permute.iter().enumerate() {
+      let row = &self.rows[i as usize];
+      for c in 0..self.columns.len() {
+        let value = row.entry(self.column_index(c));
+        if value > T::ZERO {
+          entries.push((r, c, value));
+        }
+      }
+    }
+    entries
   }
 
   #[inline(always)]
@@ -117,43 +1127,171 @@ impl DiophantineSystem {
     self.columns.len()
   }
 
-  pub fn insert_row(&mut self, coeff: u32, min_size: u32, max_size: u32) {
-    assert!(!self.closed);
-    assert!(coeff > 0);
-    // assert!(min_size >= 0);
-    assert!(min_size <= max_size);
+  /// Adds a row with coefficient `coeff` whose sum must lie in `[min_size, max_size]`. A `max_size` of `UNBOUNDED`
+  /// means the row sum is bounded only by the column total.
+  pub fn insert_row(&mut self, coeff: T, min_size: T, max_size: T) -> Result<(), DiophantineError> {
+    if self.closed {
+      return Err(DiophantineError::SystemClosed);
+    }
+    let new_row = Self::new_row(self.rows.len(), coeff, min_size, max_size)?;
+    self.rows.push(new_row);
+    Ok(())
+  }
+
+  /// Validates the arguments of `insert_row` and builds the row inserted `i`th.
+  fn new_row(i: usize, coeff: T, min_size: T, max_size: T) -> Result<Row<T>, DiophantineError> {
+    if coeff <= T::ZERO || min_size > max_size {
+      return Err(DiophantineError::InvalidRow);
+    }
+    if coeff.checked_mul(min_size).is_none()
+        || (max_size != T::UNBOUNDED && coeff.checked_mul(max_size).is_none())
+    {
+      return Err(DiophantineError::Overflow);
+    }
 
-    let row_count = self.rows.len();
-    let new_row = Row{
-      name: row_count as u32,
+    Ok(Row{
+      name: i as u32,
       coeff,
       min_size,
       max_size,
+      unbounded: max_size == T::UNBOUNDED,
+      inserted_min_size: min_size,
+      inserted_max_size: max_size,
       ..Default::default()
-    };
-
-    self.rows.push(new_row);
+    })
   }
 
-  pub fn insert_column(&mut self, value: u32) {
-    assert!(value > 0);
-    assert!(!self.closed);
+  /// Adds a column with value `value`. A zero column is allowed: every row assigns it zero, as for a constant of an
+  /// ACU matching problem that need not be matched.
+  pub fn insert_column(&mut self, value: T) -> Result<(), DiophantineError> {
+    if self.closed {
+      return Err(DiophantineError::SystemClosed);
+    }
+    if value < T::ZERO {
+      return Err(DiophantineError::NegativeColumn);
+    }
+    let column_sum = self.column_sum.checked_add(value).ok_or(DiophantineError::Overflow)?;
 
     self.columns.push(value);
-    self.column_sum += value;
+    self.original_columns.push(value);
+    self.column_sum = column_sum;
     if value > self.max_column_value {
         self.max_column_value = value;
     }
+    Ok(())
   }
-  // Check for trivial failure, sort R, fill out row_permute vector, compute
-  // min_leave and max_leave values and allocate and initialize selection vectors.
-  // For complex system we also build solubility vectors and check each compontent
-  // of C for trivial failure.
-  fn precompute(&mut self) -> bool {
-    #[cfg(feature = "TRACE_CALLS")]
-    println!("precompute");
-    assert!(self.rows.len() > 0);
-    assert!(self.columns.len() > 0);
+
+  /// Adds the columns in order as if by `insert_column`, stopping at the first one rejected.
+  pub fn extend_columns<I: IntoIterator<Item = T>>(&mut self, columns: I) -> Result<(), DiophantineError> {
+    for value in columns {
+      self.insert_column(value)?;
+    }
+    Ok(())
+  }
+
+  /// Returns a fresh system with the same rows and settings as this one, but with columns `columns` in place of its
+  /// own, as if built anew. For many right-hand sides against the same rows, this saves rebuilding the solubility
+  /// vectors: once this system has been prepared for the search (by `solve()` or `classify()`), the new system reuses
+  /// them as long as none of its columns is larger than the largest column here. Fails with
+  /// `DiophantineError::ColumnCountMismatch` if the number of columns differs.
+  pub fn with_new_columns(&self, columns: &[T]) -> Result<Self, DiophantineError> {
+    if columns.len() != self.original_columns.len() {
+      return Err(DiophantineError::ColumnCountMismatch { expected: self.original_columns.len(), found: columns.len() });
+    }
+    let mut column_sum = T::ZERO;
+    for &value in columns.iter() {
+      if value < T::ZERO {
+        return Err(DiophantineError::NegativeColumn);
+      }
+      column_sum = column_sum.checked_add(value).ok_or(DiophantineError::Overflow)?;
+    }
+
+    let mut system = self.clone();
+    system.original_columns.copy_from_slice(columns);
+    system.reset();
+    Ok(system)
+  }
+
+  /// Builds a system from rows `(coeff, min_size, max_size)` and columns, like `insert_row()` and `insert_column()`
+  /// in turn, with the solubility vectors `tables` taken from another system by `extract_solubility()`. The system
+  /// uses them instead of building its own as long as it has the same rows, in the same search order, and none of its
+  /// columns exceeds `tables.max_column_value()`, as with `with_new_columns()`; otherwise the tables are ignored. The
+  /// solutions are the same either way. Fails if a row or column would fail to insert.
+  pub fn with_solubility(
+    rows    : &[(T, T, T)],
+    columns : &[T],
+    tables  : &SolubilityTables<T>,
+  ) -> Result<Self, DiophantineError> {
+    let mut system = DiophantineSystem::with_capacity(rows.len(), columns.len());
+    for &(coeff, min_size, max_size) in rows.iter() {
+      system.insert_row(coeff, min_size, max_size)?;
+    }
+    for &column in columns.iter() {
+      system.insert_column(column)?;
+    }
+
+    // The vectors depend on the coefficients, which precompute() takes to be those they were built for.
+    let compatible = tables.rows.len() == rows.len()
+        && tables.rows.iter().all(|&(name, coeff, _)| rows[name as usize].0 == coeff);
+    if compatible {
+      for (&(name, _, _), vector) in tables.rows.iter().zip(tables.vectors.iter()) {
+        system.rows[name as usize].soluble = vector.clone();
+      }
+      system.soluble_rows = tables.rows.iter().map(|&(name, _, max_size)| (name, max_size)).collect();
+    }
+    Ok(system)
+  }
+
+  /// Adds a column with value `value`, even after solving has started, and restarts the search for the extended
+  /// system, whose next call to `solve()` finds its first solution. This is for matching against subjects that arrive
+  /// one at a time. The search state is discarded as by `reset()`, so the solutions found so far, which are not
+  /// solutions of the extended system, are forgotten along with the statistics, while the settings, bounds and fixed
+  /// entries of the system are kept. What carries over is the precomputation: the solubility vectors of a complex
+  /// system are reused as long as `value` is no larger than the largest column so far.
+  pub fn push_column_and_resolve(&mut self, value: T) -> Result<(), DiophantineError> {
+    if value < T::ZERO {
+      return Err(DiophantineError::NegativeColumn);
+    }
+    // The inserted columns were checked not to overflow their sum, so only the new one can.
+    let column_sum = self.original_columns.iter().fold(T::ZERO, |sum, &column| sum + column);
+    if column_sum.checked_add(value).is_none() {
+      return Err(DiophantineError::Overflow);
+    }
+
+    self.reset();
+    self.insert_column(value)
+  }
+
+  /// Changes the value of the column inserted `j`th, as if it had been inserted with `value`. Allowed only before the
+  /// first call to `solve()` or after `reset()`, so that a family of systems differing only in their columns can be
+  /// solved without rebuilding the rows. Fails with `DiophantineError::IndexOutOfRange` if the column does not exist.
+  pub fn set_column(&mut self, j: usize, value: T) -> Result<(), DiophantineError> {
+    if self.closed {
+      return Err(DiophantineError::SystemClosed);
+    }
+    if value < T::ZERO {
+      return Err(DiophantineError::NegativeColumn);
+    }
+    if j >= self.original_columns.len() {
+      return Err(self.index_out_of_range(0, j));
+    }
+    let column_sum = (self.column_sum - self.original_columns[j]).checked_add(value).ok_or(DiophantineError::Overflow)?;
+
+    self.columns[j]          = value;
+    self.original_columns[j] = value;
+    self.column_sum          = column_sum;
+    self.max_column_value    = self.original_columns.iter().fold(T::ZERO, |m, &column| max(m, column));
+    Ok(())
+  }
+  // Check for trivial failure, sort R, fill out row_permute vector, compute
+  // min_leave and max_leave values and allocate and initialize selection vectors.
+  // For complex system we also build solubility vectors and check each compontent
+  // of C for trivial failure.
+  fn precompute(&mut self) -> bool {
+    #[cfg(feature = "TRACE_CALLS")]
+    println!("precompute");
+    assert!(!self.rows.is_empty());
+    assert!(!self.columns.is_empty());
 
     self.closed = true;
 
@@ -170,205 +1308,1044 @@ impl DiophantineSystem {
       println!();
     }
 
-    let mut sum_of_min_products = 0;
-    let mut sum_of_max_products = 0;
+    let mut sum_of_min_products = T::ZERO;
+    let mut sum_of_max_products = T::ZERO;
+
+    for r in self.rows.iter_mut() {
+      if r.max_size == T::UNBOUNDED {
+        r.max_size = self.column_sum;
+      }
+      // `insert_row` rules out overflow of the products of bounded rows, but an unbounded row's product and the sums
+      // can still overflow. A saturated value exceeds every column total, so the checks below remain sound.
+      r.min_product = r.min_size * r.coeff;
+      sum_of_min_products = sum_of_min_products.saturating_add(r.min_product);
+      r.max_product = r.max_size.saturating_mul(r.coeff);
+      sum_of_max_products = sum_of_max_products.saturating_add(r.max_product);
+    }
+
+    if !self.lex {
+      self.sort_rows();
+    }
+    self.coeff_descending = self.rows.windows(2).all(|pair| pair[0].coeff >= pair[1].coeff);
+    self.row_permute.resize(self.rows.len(), 0);
+
+    let mut min_total: T = T::ZERO;
+    let mut max_total: T = T::ZERO;
+    for (i, row) in self.rows.iter_mut().enumerate().rev() {
+      self.row_permute[row.name as usize] = i as u32;
+      row.min_leave = min_total;
+      row.max_leave = max_total;
+      row.selection.resize(self.columns.len(), Select::default());
+      for s in row.selection.iter_mut(){
+        s.base = T::ZERO;
+      }
+      min_total = min_total.saturating_add(row.min_product);
+      max_total = max_total.saturating_add(row.max_product);
+    }
+
+    self.complex = self.rows.last().unwrap().coeff > T::ONE
+        || self.rows.last().unwrap().max_size < self.max_column_value;
+
+    if sum_of_min_products > self.column_sum {
+      self.infeasibility = Some(Infeasibility::MinProductsTooLarge {
+        sum_of_min : sum_of_min_products,
+        column_sum : self.column_sum,
+      });
+    } else if sum_of_max_products < self.column_sum {
+      self.infeasibility = Some(Infeasibility::MaxProductsTooSmall {
+        sum_of_max : sum_of_max_products,
+        column_sum : self.column_sum,
+      });
+    }
+    if self.infeasibility.is_some() {
+      self.failed = true;
+      // println!("Precompute failed #1");
+      // self.dump_info();
+      return false;
+    }
+
+    if self.complex {
+      if !self.solubility_memory_fits() {
+        self.too_large = true;
+        self.failed    = true;
+        return false;
+      }
+      if !self.reuse_solubility_vectors() {
+        if !self.reserve_solubility_vectors() {
+          self.allocation_failed = true;
+          self.failed            = true;
+          return false;
+        }
+        self.build_solubility_vectors();
+        if self.invariant_violated {
+          return false;
+        }
+      }
+      let soluble = &self.rows[0].soluble;
+
+      for (j, &column) in self.columns.iter().enumerate() {
+        if !soluble.is_soluble(column.to_usize()) {
+          self.failed = true;
+          self.infeasibility = Some(Infeasibility::InsolubleColumn {
+            column : self.column_permute.iter().position(|&p| p as usize == j).unwrap_or(j),
+            value  : column,
+          });
+          // println!("Precompute failed #2");
+          // self.dump_info();
+          return false;
+        }
+      }
+    }
+
+    // self.dump_info();
+    true
+  }
+
+
+  /// Sorts the rows into the search order given by `row_order`. Rows are in insertion order beforehand, and the sorts
+  /// are stable, so ties stay in insertion order.
+  fn sort_rows(&mut self) {
+    match self.row_order {
+      RowOrder::DescCoeff      => self.rows.sort(),
+      RowOrder::DescMaxProduct => self.rows.sort_by(|a, b| b.max_product.cmp(&a.max_product).then_with(|| a.cmp(b))),
+      RowOrder::Custom(compare) => {
+        let spec = |row: &Row<T>| RowSpec {
+          coeff    : row.coeff,
+          min_size : row.inserted_min_size,
+          max_size : row.inserted_max_size,
+        };
+        self.rows.sort_by(|a, b| compare(&spec(a), &spec(b)));
+      }
+    }
+  }
+
+
+  #[cfg(feature = "std")]
+  pub fn dump_info(&self){
+    println!(
+      "row permute: {:?}\nclosed: {}\n column_sum: {}\ncomplex: {}\nfailed: {}\nmax_column_value: {}",
+      self.row_permute,
+      self.closed,
+      self.column_sum,
+      self.complex,
+      self.failed,
+      self.max_column_value
+    );
+    println!("Rows:");
+    for row in &self.rows {
+      println!("{}", row);
+    }
+    println!("columns: {:?}", self.columns);
+  }
+
+
+  /// Identifies the rows, in search order, as far as the solubility vectors are concerned. An unbounded row's
+  /// `max_size` is the column sum, which never limits what a row can take from a single column.
+  fn soluble_rows(&self) -> Vec<(u32, T)> {
+    self.rows
+        .iter()
+        .map(|row| (row.name, if row.unbounded { T::UNBOUNDED } else { row.max_size }))
+        .collect()
+  }
+
+  /// Whether the solubility vectors, one entry per column value up to `max_column_value` for each row, fit within
+  /// `solubility_memory`.
+  fn solubility_memory_fits(&self) -> bool {
+    if !SolubilityVector::can_hold(self.max_column_value) {
+      return false;
+    }
+    match self.solubility_memory {
+      None        => true,
+      Some(bytes) => {
+        let entries = self.max_column_value.to_usize().checked_add(1).and_then(|n| n.checked_mul(self.rows.len()));
+        entries
+          .and_then(|n| n.checked_mul(SolubilityVector::<T>::ENTRY_BYTES))
+          .is_some_and(|needed| needed <= bytes)
+      }
+    }
+  }
+
+  /// Keeps the solubility vectors of an earlier search if they were built for the same rows in the same order and
+  /// cover every column value, truncating them to the current `max_column_value`. Entries for smaller column values do
+  /// not depend on larger ones, so a prefix of a solubility vector is the solubility vector for smaller columns.
+  fn reuse_solubility_vectors(&mut self) -> bool {
+    let length = self.max_column_value.to_usize() + 1;
+    if self.soluble_rows != self.soluble_rows() || self.rows.iter().any(|row| row.soluble.len() < length) {
+      return false;
+    }
+    for row in self.rows.iter_mut() {
+      row.soluble.truncate(length);
+    }
+    true
+  }
+
+  /// Allocates room for a solubility vector of every row, up to `max_column_value`, without aborting if the memory
+  /// cannot be had. On failure, releases what was allocated and returns false.
+  fn reserve_solubility_vectors(&mut self) -> bool {
+    let length = self.max_column_value.to_usize() + 1;
+    if self.rows.iter_mut().all(|row| row.soluble.try_reserve(length)) {
+      return true;
+    }
+    for row in self.rows.iter_mut() {
+      row.soluble = SolubilityVector::default();
+    }
+    false
+  }
+
+  // Function to build the solubility vectors discussed in [README.md] using a dynamic
+  // programming approach.
+  fn build_solubility_vectors(&mut self) {
+    self.soluble_rows = self.soluble_rows();
+    // Compute solubility vector for last row
+    {
+      #[cfg(feature = "TRACE_CALLS")]
+      println!("build_solubility_vectors");
+      let r         : &mut Row<T>               = self.rows.last_mut().unwrap();
+      let s         : &mut SolubilityVector<T> = &mut r.soluble;
+      let coeff     : T                         = r.coeff;
+      let mut count : T                         = T::ZERO;
+
+      s.reset(self.max_column_value.to_usize() + 1);
+
+      let mut j = T::ZERO;
+      while j <= self.max_column_value && count <= r.max_size {
+        s.set_min(j.to_usize(), count);
+        s.set_max(j.to_usize(), count);
+        count += T::ONE;
+        j += coeff;
+      }
+    }
+
+    // Compute remaining vectors in descending order
+    for i in (0..self.rows.len() - 1).rev() {
+
+      let max_size  : T = self.rows[i].max_size;
+      let coeff     : T = self.rows[i].coeff;
+
+      // Get mutable access to two elements at once.
+      let (lower, upper) = self.rows.split_at_mut(i + 1);
+      let next: &mut SolubilityVector<T> = &mut lower.last_mut().unwrap().soluble; // self.rows[row_idx].soluble;
+      let prev: &mut SolubilityVector<T> = &mut upper.first_mut().unwrap().soluble; // self.rows[row_idx + 1].soluble;
+
+      next.reset(self.max_column_value.to_usize() + 1);
+
+      for j in 0..=self.max_column_value.to_usize() {
+        if let Some(t) = j.checked_sub(coeff.to_usize()) {
+          if next.is_soluble(t) && (max_size == T::UNBOUNDED || next.min(t) < max_size) {
+            let min = if prev.is_soluble(j) { T::ZERO } else { next.min(t) + T::ONE };
+            next.set_min(j, min);
+
+            if max_size == T::UNBOUNDED || next.max(t) < max_size {
+              next.set_max(j, next.max(t) + T::ONE);
+            }
+            else {
+              let mut new_max: T = max_size;
+
+              for k in ((j - (max_size * coeff).to_usize())..j).step_by(coeff.to_usize()) {
+                if !prev.is_soluble(k) {
+                  new_max -= T::ONE;
+                } else {
+                  break;
+                }
+              }
+
+              invariant!(self, new_max > next.min(t), "new_max <= min");
+              next.set_max(j, new_max);
+            }
+
+          } else {
+            let v = if prev.is_soluble(j) { T::ZERO } else { Soluble::INSOLUBLE };
+
+            next.set_min(j, v);
+            next.set_max(j, v);
+          }
+        } else {
+          let v = if prev.is_soluble(j) { T::ZERO } else { Soluble::INSOLUBLE };
+
+          next.set_min(j, v);
+          next.set_max(j, v);
+        }
+
+      }
+    }
+  }
+
+
+
+  /// Finds the next solution, returning false if there are no more. Once it has returned false, it keeps doing so until
+  /// the system is `reset()`.
+  ///
+  /// A system without rows or without columns has at most one solution, the empty matrix. It is a solution when every
+  /// column is zero and no row is required to be nonempty, that is, every row's `min_size` is zero (taking the
+  /// extension row into account).
+  ///
+  /// Solutions come in a fixed order, the same on every run and after `reset()`. The rows are assigned one at a time
+  /// in the order given by `row_permutation()`, depth first, and the last row takes what is left. Each row runs
+  /// through its possible sums in increasing order and, for each sum, through its possible entries in increasing
+  /// colexicographic order, comparing the last column first, so that the earliest columns are filled first. Row
+  /// merging changes the order. For solutions in lexicographic order of `solution_flat()`, use `solve_lex()`.
+  pub fn solve(&mut self) -> bool {
+    if self.solution_delta {
+      let mut previous = core::mem::take(&mut self.previous_solution);
+      previous.clear();
+      if self.has_current_solution() {
+        previous.extend(self.iter_solution_cells());
+      }
+      self.previous_solution = previous;
+    }
+
+    if !self.closed && !self.start() {
+      self.search_started = true;
+      return false;
+    }
+    let find_first = !self.search_started;
+    self.search_started = true;
+    if self.failed {
+      // Exhausted, interrupted, or found insoluble by `classify()`.
+      return false;
+    }
+
+    if let Some(limit) = self.solution_limit {
+      if self.stats.solutions_found >= limit {
+        self.failed = true;
+        return false;
+      }
+    }
+
+    self.budget_start = self.stats.nodes_visited;
+    let found = self.search(find_first);
+    if found {
+      self.stats.solutions_found += 1;
+    }
+    found
+  }
+
+  /// Like `solve()`, but distinguishes running out of the node budget, reported as `DiophantineError::Interrupted`,
+  /// from running out of solutions. Once interrupted, the search cannot continue; every further call returns
+  /// `Interrupted` until the system is `reset()`. A system whose solubility vectors would exceed the limit set by
+  /// `set_max_solubility_memory()` is likewise reported as `DiophantineError::TooLarge`, one whose solubility vectors
+  /// cannot be allocated as `DiophantineError::AllocationFailed`, rather than aborting the process as `solve()` would
+  /// on other allocations, and with the `debug_checks` feature, a bug caught by the solver's internal checks as
+  /// `DiophantineError::InternalInvariant`.
+  pub fn try_solve(&mut self) -> Result<bool, DiophantineError> {
+    if let Some(error) = self.search_error() {
+      return Err(error);
+    }
+    let found = self.solve();
+    if let Some(error) = self.search_error() {
+      return Err(error);
+    }
+    Ok(found)
+  }
+
+  /// Same as `solve()`, but checks each solution found with `verify_current_solution()` and panics, showing the
+  /// solution, if it is not a solution of the system. For fuzzing and tests, where it turns a bug in the search into a
+  /// failure.
+  pub fn solve_checked(&mut self) -> bool {
+    let found = self.solve();
+    if found && !self.verify_current_solution() {
+      panic!(
+        "solve() found an invalid solution\n  M = {:?}\n  R * M = {:?}\n  C = {:?}\n  row bounds = {:?}",
+        self.solution_matrix(),
+        self.reconstructed_columns(),
+        self.original_columns,
+        self.spec().rows.iter().map(|row| (row.min_size, row.max_size)).collect::<Vec<_>>(),
+      );
+    }
+    found
+  }
+
+  /// Like `solve()`, but tells running out of solutions apart from running out of the node budget. Searches stopped by
+  /// the other errors of `try_solve()` are reported as `Exhausted`.
+  pub fn solve_outcome(&mut self) -> SolveOutcome {
+    if self.solve() {
+      SolveOutcome::Solution
+    } else if self.interrupted {
+      SolveOutcome::Interrupted
+    } else {
+      SolveOutcome::Exhausted
+    }
+  }
+
+  /// Why the search stopped, if it stopped for a reason other than running out of solutions.
+  fn search_error(&self) -> Option<DiophantineError> {
+    if self.interrupted {
+      Some(DiophantineError::Interrupted)
+    } else if self.invariant_violated {
+      Some(DiophantineError::InternalInvariant)
+    } else if self.too_large {
+      Some(DiophantineError::TooLarge)
+    } else if self.allocation_failed {
+      Some(DiophantineError::AllocationFailed)
+    } else {
+      None
+    }
+  }
+
+  /// Bounds the work of each call to `solve()`: once it has visited more than `budget` search nodes without finding
+  /// the next solution, it gives up. `solve()` then returns false as if the search were exhausted, while `try_solve()`
+  /// returns `DiophantineError::Interrupted`. A node is one attempt to find a selection for a row. `None` removes the
+  /// budget. The budget must be set before the first call to `solve()`, and survives `reset()`.
+  pub fn set_node_budget(&mut self, budget: Option<u64>) -> Result<(), DiophantineError> {
+    if self.closed {
+      return Err(DiophantineError::SystemClosed);
+    }
+    self.node_budget = budget;
+    Ok(())
+  }
+
+  /// Limits the memory taken by the solubility vectors of a complex system, which hold an entry for every value up to
+  /// the largest column for every row, the size of a `Soluble<T>` each or eight bytes with the `compact_solubility`
+  /// feature. If they would take more than `bytes`, they are not built: `solve()` returns
+  /// false as if the system had no solutions, while `try_solve()` returns `DiophantineError::TooLarge`. `None` removes
+  /// the limit. With row merging, the limit applies to the merged system. The limit must be set before the first call
+  /// to `solve()`, and survives `reset()`.
+  pub fn set_max_solubility_memory(&mut self, bytes: Option<usize>) -> Result<(), DiophantineError> {
+    if self.closed {
+      return Err(DiophantineError::SystemClosed);
+    }
+    self.solubility_memory = bytes;
+    Ok(())
+  }
+
+  /// Whether the solubility vectors would have exceeded the limit set by `set_max_solubility_memory()`.
+  pub(crate) fn too_large(&self) -> bool {
+    self.too_large
+  }
+
+  /// Whether the solubility vectors could not be allocated.
+  pub(crate) fn allocation_failed(&self) -> bool {
+    self.allocation_failed
+  }
+
+  /// Whether the last call to `solve()` gave up because the node budget ran out.
+  pub fn interrupted(&self) -> bool {
+    self.interrupted
+  }
+
+  /// Whether the search has run out of solutions: `solve()` has returned false, and will keep doing so until the
+  /// system is `reset()`. A search that gave up on its node budget is `interrupted()`, not exhausted, and neither is
+  /// one stopped by an error reported by `try_solve()`.
+  pub fn is_exhausted(&self) -> bool {
+    self.search_started && self.failed && self.search_error().is_none()
+  }
+
+  /// Whether the last call to `solve()` returned true, so that `solution()` reads a valid solution.
+  pub fn has_current_solution(&self) -> bool {
+    self.search_started && !self.failed
+  }
+
+  /// Checks the node budget, marking the search as interrupted and failed if it has run out.
+  #[inline(always)]
+  fn out_of_budget(&mut self) -> bool {
+    match self.node_budget {
+      Some(budget) if self.stats.nodes_visited - self.budget_start > budget => {
+        self.interrupted = true;
+        self.failed      = true;
+        true
+      }
+      _ => false,
+    }
+  }
+
+  /// Like `solve()`, but produces the solutions in increasing lexicographic order of `solution_flat()`, that is, of
+  /// the solution matrix read row by row in insertion order. The rows are searched in insertion order, without the
+  /// sorting and pruning that make `solve()` faster, and row merging is not used. A search started with `solve_lex()`
+  /// must be continued with `solve_lex()`, or with `solve()`, which then continues in the same order; switching to
+  /// `solve_lex()` after `solve()` panics.
+  pub fn solve_lex(&mut self) -> bool {
+    if !self.closed {
+      self.lex = true;
+    }
+    assert!(self.lex, "solve_lex() called on a search started by solve()");
+    self.solve()
+  }
+
+  /// Like `solve()`, but on a fresh system first constrains the rows inserted at the indices in `zero_rows` to sum to
+  /// zero, as if they had been inserted with a `max_size` of zero. The solutions are then those in which the variables
+  /// of these rows are assigned nothing, as for an ACU matching problem in which they are bound to the identity, while
+  /// the default AC case wants every row nonempty. A listed row other than the extension row that must be nonempty
+  /// leaves no solutions. Once the search has started, the rows are already constrained and `zero_rows` is ignored;
+  /// the constraint survives `reset()`. The rows keep their bounds as inserted, as reported by `row_max_size()`, and
+  /// `spec()` lists them among its `zero_rows`. Fails with `DiophantineError::IndexOutOfRange` if a listed row does
+  /// not exist, leaving the system untouched.
+  pub fn solve_with_zero_rows(&mut self, zero_rows: &[usize]) -> Result<bool, DiophantineError> {
+    if !self.closed {
+      self.constrain_zero_rows(zero_rows)?;
+    }
+    Ok(self.solve())
+  }
+
+  /// Marks the rows inserted at the indices in `zero_rows` of an open system to be constrained to sum to zero once the
+  /// search starts, for `solve_with_zero_rows()`. Marks nothing if a listed row does not exist.
+  pub(crate) fn constrain_zero_rows(&mut self, zero_rows: &[usize]) -> Result<(), DiophantineError> {
+    if let Some(&i) = zero_rows.iter().find(|&&i| i >= self.rows.len()) {
+      return Err(self.index_out_of_range(i, 0));
+    }
+    for &i in zero_rows {
+      self.rows[i].zero = true;
+    }
+    Ok(())
+  }
+
+  /// Like `solve()`, but produces the solutions in increasing order of their total, the sum of all entries of $M$,
+  /// and those with equal totals in the order of `solve()`. Rather than keeping a frontier of partial solutions, whose
+  /// size can grow with the number of solutions, the search is run once for each distinct total, from scratch, skipping
+  /// the solutions of other totals and noting the least greater total it passes. The extra memory is constant, but
+  /// enumerating every solution costs one full search per distinct total, plus one to find the least. When the
+  /// solutions fit in memory, collecting them and sorting by total is faster. The statistics and the solution limit
+  /// apply to each search in turn. A search started with `solve_by_total_ascending()` must be continued with it;
+  /// calling it after `solve()` has started a search panics.
+  pub fn solve_by_total_ascending(&mut self) -> bool {
+    let (mut total, mut next) = match self.total_order {
+      Some(state) => state,
+      None        => {
+        assert!(!self.search_started, "solve_by_total_ascending() called on a search started by solve()");
+        (T::ZERO, None)
+      }
+    };
+
+    loop {
+      while self.solve() {
+        let sum = self.iter_solution_cells().fold(T::ZERO, |sum, entry| sum + entry);
+        if sum == total {
+          self.total_order = Some((total, next));
+          return true;
+        }
+        if sum > total && next.is_none_or(|next| sum < next) {
+          next = Some(sum);
+        }
+      }
+
+      match next {
+        Some(greater) if self.search_error().is_none() => {
+          self.reset();
+          total = greater;
+          next  = None;
+        }
+        _ => {
+          self.total_order = Some((total, None));
+          return false;
+        }
+      }
+    }
+  }
+
+  /// Skips `n` solutions and finds the one after, calling `solve()` up to `n + 1` times, so that on a fresh system
+  /// `solution()` then reads the `n`th solution counting from 0. Solutions `0..n` are consumed in the process. Returns
+  /// false if there are no more than `n` solutions left.
+  pub fn solve_nth(&mut self, n: u64) -> bool {
+    for _ in 0..n {
+      if !self.solve() {
+        return false;
+      }
+    }
+    self.solve()
+  }
+
+  /// Closes the system and prepares it for the search. Returns false if the system is found to be insoluble.
+  fn start(&mut self) -> bool {
+    if self.column_sorting && !self.lex {
+      self.sort_columns();
+    }
+    if !(self.apply_extension_row() && self.apply_zero_rows() && self.apply_fixed_cells()) {
+      self.closed = true;
+      self.failed = true;
+      self.infeasibility = Some(Infeasibility::InconsistentConstraints);
+      return false;
+    }
+    if self.rows.is_empty() || self.columns.is_empty() {
+      return self.start_empty();
+    }
+    if self.row_merging && !self.lex {
+      self.start_merging();
+    }
+    if self.closed {
+      // Merging rows; the merged system is prepared by its own first call to `solve()`.
+      return true;
+    }
+    self.precompute()
+  }
+
+  /// Stores the columns in descending order of value, ties in insertion order, filling out `column_permute`.
+  fn sort_columns(&mut self) {
+    let mut order: Vec<usize> = (0..self.original_columns.len()).collect();
+    order.sort_by(|&a, &b| self.original_columns[b].cmp(&self.original_columns[a]));
+
+    self.column_permute = vec![0; order.len()];
+    for (p, &j) in order.iter().enumerate() {
+      self.column_permute[j] = p as u32;
+      self.columns[p]        = self.original_columns[j];
+    }
+    for row in self.rows.iter_mut() {
+      row.permute_columns(&self.column_permute, false);
+    }
+  }
+
+  /// Closes a system without rows or without columns, whose only possible solution is the empty matrix. Returns false
+  /// if that is not a solution.
+  fn start_empty(&mut self) -> bool {
+    self.closed      = true;
+    self.row_permute = (0..self.rows.len() as u32).collect();
+    if self.rows.is_empty() && self.column_sum > T::ZERO {
+      self.infeasibility = Some(Infeasibility::MaxProductsTooSmall {
+        sum_of_max : T::ZERO,
+        column_sum : self.column_sum,
+      });
+    } else if self.columns.is_empty() && self.rows.iter().any(|row| row.min_size > T::ZERO) {
+      let sum_of_min = self.rows
+          .iter()
+          .fold(T::ZERO, |sum, row| sum.saturating_add(row.min_size.saturating_mul(row.coeff)));
+      self.infeasibility = Some(Infeasibility::MinProductsTooLarge { sum_of_min, column_sum: T::ZERO });
+    }
+    if self.infeasibility.is_some() {
+      self.failed = true;
+      return false;
+    }
+    true
+  }
+
+  /// The first column, in insertion order, whose value no combination of the rows can make up on its own, if the
+  /// system was found insoluble for that reason. This is checked when a complex system is prepared for the search, by
+  /// the first call to `solve()` (or `classify()`), using the solubility vectors, so it is `None` for simple systems
+  /// and for systems found insoluble for some other reason. With fixed entries, the value in question is what is left
+  /// of the column after they are taken out.
+  pub fn infeasible_column(&self) -> Option<usize> {
+    match self.infeasibility {
+      Some(Infeasibility::InsolubleColumn { column, .. }) => Some(column),
+      _                                                  => None,
+    }
+  }
+
+  /// Why the system has no solutions, if that was found before the search: by the first call to `solve()` (or
+  /// `classify()` or `precompute_now()`), or with row merging, once `solve()` has returned false. `None` if the
+  /// system has not been found insoluble that way, including when the search itself finds no solution and when the
+  /// solubility vectors could not be built.
+  pub fn explain_infeasibility(&self) -> Option<Infeasibility<T>> {
+    self.infeasibility
+  }
+
+  /// Closes the system and runs the precomputation of the first call to `solve()`, which for a complex system builds
+  /// the solubility vectors, so that this potentially expensive phase can be separated from the search. Returns
+  /// `DiophantineError::Infeasible` if the precomputation finds that the system has no solutions, and the errors of
+  /// `try_solve()` if it could not be completed. Calling it again does no further work and reports the same outcome;
+  /// once the search has started, only the errors of `try_solve()` are reported.
+  pub fn precompute_now(&mut self) -> Result<(), DiophantineError> {
+    if !self.closed {
+      self.start();
+    }
+    if let Some(error) = self.search_error() {
+      return Err(error);
+    }
+    if self.failed && !self.search_started {
+      return Err(DiophantineError::Infeasible);
+    }
+    Ok(())
+  }
+
+  /// Classifies the system as simple or complex, closing it and running the precomputation of the first call to
+  /// `solve()` if that has not happened yet. A system is complex unless the row searched last, which after sorting
+  /// is the one with the smallest coefficient and among those the largest `max_size`, has coefficient 1 and a
+  /// `max_size` at least the largest column value. A simple system is solved without solubility vectors. With row
+  /// merging, this is the classification of the merged system. A system found insoluble before the rows are sorted,
+  /// because of inconsistent fixed entries or extension row, is reported as `Simple`, as is a system without rows or
+  /// without columns.
+  pub fn classify(&mut self) -> SystemClass {
+    if !self.closed {
+      self.start();
+    }
+    let complex = match self.merging.as_mut() {
+      Some(merging) => merging.is_complex(),
+      None          => self.complex,
+    };
+    if complex { SystemClass::Complex } else { SystemClass::Simple }
+  }
+
+  /// The range of sums the row inserted `i`th can have in a solution, as far as the row bounds and the column total
+  /// tell: at least `min_size`, at most `max_size`, and leaving a total the other rows can take given their own
+  /// bounds. Returns `None` if the range is empty or the system is insoluble. The range is for the system as it was
+  /// built, whatever the search has done since, and is worked out on a copy, so `self` is left untouched. It is not
+  /// necessarily tight, since a sum within it need not be achievable.
+  pub fn row_size_bounds(&self, i: usize) -> Option<(T, T)> {
+    let mut system = self.spec().to_system().expect("the spec of a valid system is valid");
+    system.fresh_row_size_bounds(i)
+  }
 
-    for r in self.rows.iter_mut() {
-      if r.max_size == UNBOUNDED {
-        r.max_size = self.column_sum;
-      }
-      r.min_product = r.min_size * r.coeff;
-      sum_of_min_products += r.min_product;
-      r.max_product = r.max_size * r.coeff;
-      sum_of_max_products += r.max_product;
+  /// `row_size_bounds()` for a system on which the search has not started, which this closes.
+  fn fresh_row_size_bounds(&mut self, i: usize) -> Option<(T, T)> {
+    if !self.closed {
+      self.start();
+    }
+    if self.failed {
+      return None;
     }
 
-    if sum_of_min_products > self.column_sum
-        || sum_of_max_products < self.column_sum
-    {
-      self.failed = true;
-      // println!("Precompute failed #1");
-      // self.dump_info();
-      return false;
+    let products = |row: &Row<T>| {
+      let max_size = if row.unbounded { self.column_sum } else { row.max_size };
+      (row.min_size * row.coeff, max_size.saturating_mul(row.coeff))
+    };
+    let row = &self.rows[self.row_index(i)];
+    let (mut others_min, mut others_max) = (T::ZERO, T::ZERO);
+    for other in self.rows.iter().filter(|other| other.name != row.name) {
+      let (min_product, max_product) = products(other);
+      others_min = others_min.saturating_add(min_product);
+      others_max = others_max.saturating_add(max_product);
     }
 
-    self.rows.sort();
-    self.row_permute.resize(self.rows.len(), 0);
+    // The row takes at most what its coefficient divides out of each column.
+    let max_sum = self.columns.iter().fold(T::ZERO, |sum, &column| sum + column / row.coeff);
+    let max_size = if row.unbounded { self.column_sum } else { row.max_size };
 
-    let mut min_total: u32 = 0;
-    let mut max_total: u32 = 0;
-    for (i, row) in self.rows.iter_mut().enumerate().rev() {
-      self.row_permute[row.name as usize] = i as u32;
-      row.min_leave = min_total as i32;
-      row.max_leave = max_total as i32;
-      row.selection.resize(self.columns.len(), Select::default());
-      for s in row.selection.iter_mut(){
-        s.base = 0;
-      }
-      min_total += row.min_product;
-      max_total += row.max_product;
+    if others_min > self.column_sum {
+      return None;
+    }
+    let mut low = row.min_size;
+    if self.column_sum > others_max {
+      low = max(low, ceiling_division(self.column_sum - others_max, row.coeff));
+    }
+    let high = min(min(max_size, max_sum), floor_division(self.column_sum - others_min, row.coeff));
+    if low > high {
+      return None;
     }
 
-    if self.rows.last().unwrap().coeff > 1
-        || self.rows.last().unwrap().max_size < self.max_column_value
-    {
-      // The complex case
-      self.build_solubility_vectors();
-      let soluble = &mut self.rows[0].soluble;
+    // Fixed entries are outside the search but count toward the row's sum.
+    Some((low + row.fixed_sum, high + row.fixed_sum))
+  }
 
-      for column in self.columns.iter() {
-        if soluble[*column as usize].min < 0 {
-          self.failed = true;
-          // println!("Precompute failed #2");
-          // self.dump_info();
-          return false;
-        }
-      }
+  /// A cheap upper bound on the number of solutions of the system as it was built, for deciding whether to enumerate
+  /// them. Each row's possible entries are counted on their own, within the row's `row_size_bounds()` and with each
+  /// entry at most what the row's coefficient divides out of its column, and the counts are multiplied together,
+  /// leaving out the largest, since any one row is determined by the others. The bound saturates at `u64::MAX`. It
+  /// takes time proportional to the number of entries times the largest possible row sum, up to a fixed cap beyond
+  /// which a row's count is just the product of the numbers of values its entries can take, and leaves `self`
+  /// untouched.
+  pub fn estimated_solution_count(&self) -> u64 {
+    let mut system = self.spec().to_system().expect("the spec of a valid system is valid");
+    if system.rows.is_empty() || system.columns.is_empty() {
+      return system.count_solutions();
+    }
 
-      self.complex = true;
+    let mut counts = Vec::with_capacity(system.rows.len());
+    for i in 0..system.rows.len() {
+      let (low, high) = match system.fresh_row_size_bounds(i) {
+        Some(bounds) => bounds,
+        None         => return 0,
+      };
+      let row    = &system.rows[system.row_index(i)];
+      let bounds : Vec<T> = (0..system.columns.len())
+          .map(|c| min(system.columns[c] / row.coeff, row.cell_max(c)))
+          .collect();
+      counts.push(count_selections(&bounds, low - row.fixed_sum, high - row.fixed_sum));
     }
 
-    // self.dump_info();
-    true
+    counts.sort_unstable();
+    counts.pop();
+    counts.iter().fold(1u64, |product, &count| product.saturating_mul(count))
   }
 
+  /// Search statistics since the system was created or last reset. With row merging, the search of the merged
+  /// system is not counted in `nodes_visited`, `backtracks` or `empty_rows`.
+  pub fn stats(&self) -> SolveStats {
+    self.stats
+  }
 
-  pub fn dump_info(&self){
-    println!(
-      "row permute: {:?}\nclosed: {}\n column_sum: {}\ncomplex: {}\nfailed: {}\nmax_column_value: {}",
-      self.row_permute,
-      self.closed,
-      self.column_sum,
-      self.complex,
-      self.failed,
-      self.max_column_value
-    );
-    println!("Rows:");
-    for row in &self.rows {
-      println!("{}", row);
+  /// Dispatches to the search appropriate for the system.
+  fn search(&mut self, find_first: bool) -> bool {
+    if self.merging.is_some() {
+      return self.solve_merged();
+    }
+    if self.rows.is_empty() || self.columns.is_empty() {
+      // The empty matrix is the only solution.
+      if !find_first {
+        self.failed = true;
+      }
+      return find_first;
     }
-    println!("columns: {:?}", self.columns);
-  }
 
+    let found = if self.complex {
+      self.solve_complex(find_first)
+    } else {
+      self.solve_simple(find_first)
+    };
 
-  // Function to build the solubility vectors discussed in [README.md] using a dynamic
-  // programming approach.
-  fn build_solubility_vectors(&mut self) {
-    // Compute solubility vector for last row
+    #[cfg(feature = "dio_stats")]
     {
-      #[cfg(feature = "TRACE_CALLS")]
-      println!("build_solubility_vectors");
-      let r         : &mut Row          = self.rows.last_mut().unwrap();
-      let s         : &mut Vec<Soluble> = &mut r.soluble;
-      let coeff     : u32               = r.coeff;
-      let mut count : u32               = 0;
-
-      s.resize(self.max_column_value as usize + 1, Soluble::INSOLUBLE_STRUCT);
-      for v in &mut s[1..] {
-        *v = Soluble::INSOLUBLE_STRUCT;
-      }
-
-      let mut j = 0;
-      while j <= self.max_column_value && count <= r.max_size {
-        s[j as usize].min = count as i32;
-        s[j as usize].max = count as i32;
-        count += 1;
-        j += coeff;
+      if found {
+        print!("success\t");
+      } else {
+        print!("failure");
       }
     }
+    found
+  }
 
-    // Compute remaining vectors in descending order
-    for i in (0..=(self.rows.len() - 2)).rev() {
+  /// Caps the number of solutions: once `limit` solutions have been found, `solve()` returns false as if the search
+  /// were exhausted. `None` removes the cap. The limit must be set before the first call to `solve()`, and survives
+  /// `reset()`.
+  pub fn set_solution_limit(&mut self, limit: Option<u64>) -> Result<(), DiophantineError> {
+    if self.closed {
+      return Err(DiophantineError::SystemClosed);
+    }
+    self.solution_limit = limit;
+    Ok(())
+  }
 
-      let max_size  : u32 = self.rows[i].max_size;
-      let coeff     : u32 = self.rows[i].coeff;
+  /// Enables or disables tracking of the previous solution for `solution_delta()`, which must be chosen before the
+  /// first call to `solve()`. With tracking, every call to `solve()` copies the current solution before moving on, so
+  /// it is off by default.
+  pub fn set_solution_delta(&mut self, track: bool) -> Result<(), DiophantineError> {
+    if self.closed {
+      return Err(DiophantineError::SystemClosed);
+    }
+    self.solution_delta = track;
+    Ok(())
+  }
 
-      // Get mutable access to two elements at once.
-      let (lower, upper) = self.rows.split_at_mut(i + 1);
-      let next: &mut Vec<Soluble> = &mut lower.last_mut().unwrap().soluble; // self.rows[row_idx].soluble;
-      let prev: &mut Vec<Soluble> = &mut upper.first_mut().unwrap().soluble; // self.rows[row_idx + 1].soluble;
+  /// Enables or disables row merging, which must be chosen before the first call to `solve()`. With row merging,
+  /// groups of rows with identical `(coeff, min_size, max_size)` are solved as a single row and the group's share of
+  /// each solution is then split among its members in every possible way. The solutions are the same as without
+  /// merging, but are produced in a different, still deterministic, order. See the `merge` module for details. This
+  /// pays off for systems with many identical rows.
+  pub fn set_row_merging(&mut self, merge: bool) -> Result<(), DiophantineError> {
+    if self.closed {
+      return Err(DiophantineError::SystemClosed);
+    }
+    self.row_merging = merge;
+    Ok(())
+  }
 
-      next.resize(self.max_column_value as usize + 1, Soluble::INSOLUBLE_STRUCT);
+  /// Enables or disables column sorting, which must be chosen before the first call to `solve()`. With column sorting,
+  /// the search stores the columns in descending order of value, so that each row's selections fill the largest
+  /// columns first. Column indices in the interface stay in insertion order (see `column_permutation()`), and the
+  /// solutions are the same, but are produced in a different order. `solve_lex()` does not sort the columns.
+  pub fn set_column_sorting(&mut self, sort: bool) -> Result<(), DiophantineError> {
+    if self.closed {
+      return Err(DiophantineError::SystemClosed);
+    }
+    self.column_sorting = sort;
+    Ok(())
+  }
 
-      for j in 0..=self.max_column_value as usize {
-        if let Some(t) = j.checked_sub(coeff as usize) {
-          if next[t].min != Soluble::INSOLUBLE && (max_size == UNBOUNDED || next[t].min < max_size as i32) {
-            next[j].min = match prev[j].min {
-              Soluble::INSOLUBLE => next[t].min + 1,
-              _ => 0,
-            };
+  /// Makes the search find a selection for the last row as it does for the others, rather than giving the last row
+  /// whatever the other rows leave. Only the last row's leave bounds, both zero, force the selection to take all that
+  /// is left, so the solutions are the same, but the general search is exercised on every row. This is a testing aid
+  /// for the search, available in tests and with the `debug_checks` feature. Must be called before the first call to
+  /// `solve()`, and survives `reset()`.
+  #[cfg(any(test, feature = "debug_checks"))]
+  pub fn disable_last_row_shortcut(&mut self) -> Result<(), DiophantineError> {
+    if self.closed {
+      return Err(DiophantineError::SystemClosed);
+    }
+    self.last_row_shortcut = false;
+    Ok(())
+  }
 
-            if max_size == UNBOUNDED || next[t].max < max_size as i32 {
-              next[j].max = next[t].max + 1;
-            }
-            else {
-              let mut new_max: i32 = max_size as i32;
+  /// Sets the order in which the rows are searched, which must be chosen before the first call to `solve()`. The
+  /// search is correct for any order, but its speed can depend a great deal on the order, and the solutions are
+  /// produced in a different order for each. The pruning of partial solutions by total column value only applies
+  /// while the rows are in descending order of coefficient, which `Custom` orders need not keep. With row merging, the
+  /// order applies to the merged rows. `solve_lex()` ignores the order and searches the rows in insertion order.
+  pub fn set_row_order(&mut self, order: RowOrder<T>) -> Result<(), DiophantineError> {
+    if self.closed {
+      return Err(DiophantineError::SystemClosed);
+    }
+    self.row_order = order;
+    Ok(())
+  }
 
-              for k in ((j - ((max_size * coeff) as usize))..j).step_by(coeff as usize) {
-                if prev[k].min == Soluble::INSOLUBLE {
-                  new_max -= 1;
-                } else {
-                  break;
-                }
-              }
+  /// Makes the row inserted `i`th the extension row, the one row whose sum may be zero. The sums of all other rows must
+  /// then be nonzero, as for the variables of an AC matching problem with an extension variable, whatever their
+  /// `min_size`. The extension row may be empty whatever its own `min_size`. Setting a new extension row replaces the
+  /// previous one. Must be called before the first call to `solve()`; the choice survives `reset()`. Fails with
+  /// `DiophantineError::IndexOutOfRange` if the row does not exist.
+  pub fn set_extension_row(&mut self, i: usize) -> Result<(), DiophantineError> {
+    if self.closed {
+      return Err(DiophantineError::SystemClosed);
+    }
+    if i >= self.rows.len() {
+      return Err(self.index_out_of_range(i, 0));
+    }
+    self.extension_row = Some(i);
+    Ok(())
+  }
 
-              assert!(new_max >= next[t].min + 1);
-              next[j].max = new_max;
-            }
+  /// Bounds the entry $M_{row,col}$ by `max`, where `row` and `col` are insertion indices of an existing row and
+  /// column. Solutions in which the variable of `row` is assigned more than `max` copies of the constant of `col` are
+  /// skipped. Must be called before the first call to `solve()`; the bound survives `reset()`. Row merging is not
+  /// used for systems with bounded entries.
+  pub fn set_cell_max(&mut self, row: usize, col: usize, max: T) -> Result<(), DiophantineError> {
+    if self.closed {
+      return Err(DiophantineError::SystemClosed);
+    }
+    if row >= self.rows.len() || col >= self.columns.len() {
+      return Err(self.index_out_of_range(row, col));
+    }
 
-          } else {
-            let v = match prev[j].min {
-              Soluble::INSOLUBLE => Soluble::INSOLUBLE,
-              _ => 0,
-            };
+    let cell_max = &mut self.rows[row].cell_max;
+    if cell_max.len() <= col {
+      cell_max.resize(col + 1, T::UNBOUNDED);
+    }
+    cell_max[col] = max;
+    Ok(())
+  }
 
-            next[j].min = v;
-            next[j].max = v;
-          }
-        } else {
-          let v = match prev[j].min {
-            Soluble::INSOLUBLE => Soluble::INSOLUBLE,
-            _ => 0,
-          };
+  /// Limits the row inserted `i`th to at most `max` nonzero entries, fixed entries included, as for a variable that
+  /// may match at most `max` distinct subject terms, however many copies of each. This differs from `max_size`, which
+  /// bounds the sum of the row's entries. Solutions with more nonzero entries in the row are skipped as the search
+  /// reaches them, so the limit does not speed up the search. Must be called before the first call to `solve()`; the
+  /// limit survives `reset()`. Row merging is not used for systems with such limits. Fails with
+  /// `DiophantineError::IndexOutOfRange` if the row does not exist.
+  pub fn set_row_cell_count_max(&mut self, i: usize, max: usize) -> Result<(), DiophantineError> {
+    if self.closed {
+      return Err(DiophantineError::SystemClosed);
+    }
+    if i >= self.rows.len() {
+      return Err(self.index_out_of_range(i, 0));
+    }
+    self.rows[i].cell_count_max = Some(max);
+    Ok(())
+  }
 
-          next[j].min = v;
-          next[j].max = v;
-        }
+  /// Fixes the entry $M_{row,col}$ to `value`, where `row` and `col` are insertion indices of an existing row and
+  /// column, as for an assignment already committed to by incremental matching. The search enumerates only the
+  /// completions consistent with the fixed entries. If the fixed entries are inconsistent with the system, the first
+  /// call to `solve()` returns false. The row bounds apply to the whole row, fixed entries included. Must be called
+  /// before the first call to `solve()`; the fixed entry survives `reset()`. Row merging is not used for systems with
+  /// fixed entries.
+  pub fn fix_cell(&mut self, row: usize, col: usize, value: T) -> Result<(), DiophantineError> {
+    if self.closed {
+      return Err(DiophantineError::SystemClosed);
+    }
+    if row >= self.rows.len() || col >= self.columns.len() {
+      return Err(self.index_out_of_range(row, col));
+    }
 
-      }
+    let row = &mut self.rows[row];
+    if row.fixed.len() <= col {
+      row.fixed.resize(col + 1, None);
     }
+    if let Some(previous) = row.fixed[col] {
+      row.fixed_sum -= previous;
+    }
+    row.fixed[col] = Some(value);
+    row.fixed_sum += value;
+    Ok(())
   }
 
+  /// Takes the fixed entries out of the problem: their contributions are subtracted from the columns and their sums
+  /// from the row bounds, leaving the search to find the rest of each row. Rows are still in insertion order. Returns
+  /// false if the fixed entries are inconsistent with the system.
+  fn apply_fixed_cells(&mut self) -> bool {
+    if self.rows.iter().all(|row| row.fixed.is_empty()) {
+      return true;
+    }
+
+    for row in self.rows.iter_mut() {
+      for (c, &value) in row.fixed.iter().enumerate() {
+        if let Some(value) = value {
+          let bound = row.cell_max.get(c).copied().unwrap_or(T::UNBOUNDED);
+          if value > bound || value.saturating_mul(row.coeff) > self.columns[c] {
+            return false;
+          }
+          self.columns[c] -= value * row.coeff;
+        }
+      }
 
+      if row.fixed_sum > row.max_size {
+        return false;
+      }
+      row.min_size = if row.min_size > row.fixed_sum { row.min_size - row.fixed_sum } else { T::ZERO };
+      if !row.unbounded {
+        row.max_size -= row.fixed_sum;
+      }
+    }
 
-  pub fn solve(&mut self) -> bool {
-    let find_first = !self.closed;
-    if find_first && !self.precompute() {
-      return false;
+    self.column_sum       = T::ZERO;
+    self.max_column_value = T::ZERO;
+    for &column in self.columns.iter() {
+      self.column_sum += column;
+      self.max_column_value = max(self.max_column_value, column);
     }
+    true
+  }
 
-    assert!(!self.failed);
+  /// Adjusts the minimum sums of the rows for the extension row, if there is one. Rows are still in insertion order.
+  /// Returns false if some row other than the extension row has a `max_size` of zero and so can never be nonempty.
+  fn apply_extension_row(&mut self) -> bool {
+    let extension_row = match self.extension_row {
+      Some(i) => i,
+      None    => return true,
+    };
 
-    #[cfg(feature = "dio_stats")]
-    {
-      let r = if self.complex {
-        self.solve_complex(find_first)
+    let mut viable = true;
+    for (i, row) in self.rows.iter_mut().enumerate() {
+      row.min_size = if i == extension_row {
+        T::ZERO
       } else {
-        self.solve_simple(find_first)
+        max(row.inserted_min_size, T::ONE)
       };
-      if r {
-        print!("success\t");
-      } else {
-        print!("failure");
+      viable &= row.min_size <= row.max_size;
+    }
+    viable
+  }
+
+  /// Constrains the rows listed by `solve_with_zero_rows()` to sum to zero. Returns false if one of them must be
+  /// nonempty.
+  fn apply_zero_rows(&mut self) -> bool {
+    let mut viable = true;
+    for row in self.rows.iter_mut().filter(|row| row.zero) {
+      row.max_size  = T::ZERO;
+      row.unbounded = false;
+      viable &= row.min_size == T::ZERO;
+    }
+    viable
+  }
+
+  /// Sets up row merging if any rows are identical, closing the system. Otherwise the system is left open to be
+  /// solved normally.
+  fn start_merging(&mut self) {
+    if self.rows.iter().any(|row| !row.cell_max.is_empty() || !row.fixed.is_empty() || row.cell_count_max.is_some()) {
+      return;
+    }
+    let rows: Vec<(T, T, T)> = self.rows.iter().map(|row| (row.coeff, row.min_size, row.max_size)).collect();
+    if let Some(merging) = RowMerging::new(&rows, &self.columns) {
+      // Rows are not sorted: every row is its own position and has no base.
+      self.closed      = true;
+      self.row_permute = (0..self.rows.len() as u32).collect();
+      for row in self.rows.iter_mut() {
+        row.selection.clear();
+        row.selection.resize(self.columns.len(), Select::default());
       }
-      return r;
+      let mut merging = Box::new(merging);
+      merging.set_node_budget(self.node_budget);
+      merging.set_row_order(self.row_order);
+      merging.set_max_solubility_memory(self.solubility_memory);
+      self.merging = Some(merging);
     }
+  }
 
-    if self.complex {
-      self.solve_complex(find_first)
-    } else {
-      self.solve_simple(find_first)
+  fn solve_merged(&mut self) -> bool {
+    let merging = self.merging.as_mut().unwrap();
+    if !merging.advance() {
+      self.interrupted = merging.interrupted();
+      self.too_large   = merging.too_large();
+      self.allocation_failed = merging.allocation_failed();
+      // The merged system's columns are these columns as stored for the search.
+      let column_permute = &self.column_permute;
+      self.infeasibility = merging.infeasibility().map(|infeasibility| match infeasibility {
+        Infeasibility::InsolubleColumn { column, value } => Infeasibility::InsolubleColumn {
+          column: column_permute.iter().position(|&p| p as usize == column).unwrap_or(column),
+          value,
+        },
+        other => other,
+      });
+      self.failed      = true;
+      return false;
     }
+
+    let rows = &mut self.rows;
+    merging.write_solution(|r, c, value| rows[r].selection[c].extra = value);
+    true
   }
 
   /// For each initial segment of the unsolved portion of R we check that there
@@ -377,17 +2354,21 @@ impl DiophantineSystem {
   /// solution fails this test (and must therefore fail).
   #[inline]
   fn viable(&self, row_idx: usize) -> bool {
-    let mut local_sum_of_min_products = 0;
+    if !self.coeff_descending {
+      // The test relies on the rows being sorted by descending coefficient.
+      return true;
+    }
+    let mut local_sum_of_min_products = T::ZERO;
 
 
     'okay:
     for row in self.rows[row_idx .. (self.rows.len() - 1)].iter() {
       let t = row.min_product;
 
-      if t > 0 {
+      if t > T::ZERO {
         local_sum_of_min_products += t;
         let lower_limit = row.coeff;
-        let mut local_column_sum = 0;
+        let mut local_column_sum = T::ZERO;
         for c in self.columns.iter() {
           if *c >= lower_limit {
             local_column_sum += *c;
@@ -407,14 +2388,31 @@ impl DiophantineSystem {
 
   // region  The Simple Case
 
-  /// Solve last row by allocating what is left.
+  /// Whether the residual columns account for the inserted columns exactly: each residual plus what the first
+  /// `assigned` rows in search order take from the column, and what the other rows are fixed to take, must be the
+  /// inserted value. This holds whenever the first `assigned` rows have their selections.
+  #[cfg(feature = "debug_checks")]
+  fn columns_conserved(&self, assigned: usize) -> bool {
+    (0..self.original_columns.len()).all(|j| {
+      let c     = self.column_index(j);
+      let taken = self.rows.iter().enumerate().try_fold(self.columns[c], |total, (i, row)| {
+        let entry = if i < assigned { row.entry(c) } else { row.fixed_value(c).unwrap_or(T::ZERO) };
+        entry.checked_mul(row.coeff).and_then(|product| total.checked_add(product))
+      });
+      taken == Some(self.original_columns[j])
+    })
+  }
+
+  /// Solve last row by allocating what is left. Returns false if that violates a bound on an entry of the row or on
+  /// the number of its nonzero entries.
   #[inline]
-  fn solve_last_row_simple(&mut self) {
-    let selection = &mut self.rows.last_mut().unwrap().selection;
+  fn solve_last_row_simple(&mut self) -> bool {
+    let row = self.rows.last_mut().unwrap();
 
-    for i in 0..self.columns.len() {
-      selection[i].extra = self.columns[i];
+    for (s, column) in row.selection.iter_mut().zip(self.columns.iter()) {
+      s.extra = *column;
     }
+    row.respects_cell_max() && row.respects_cell_count_max()
   }
 
 
@@ -425,48 +2423,54 @@ impl DiophantineSystem {
   #[inline]
   fn solve_row_simple(&mut self, row_idx: usize, find_first: bool) -> bool {
     #[cfg(feature = "TRACE_CALLS")]
-    println!("sovle_row_simple");
+    println!("solve_row_simple");
+    self.stats.nodes_visited += 1;
+    if self.rows[row_idx].max_size == T::ZERO {
+      return self.solve_row_empty(row_idx, find_first);
+    }
     if find_first {
       if ! self.viable(row_idx) {
         return false;
       }
-      let     r             : &mut Row = &mut self.rows[row_idx];
-      let mut column_total  : u32      = 0;
-      let mut max_sum       : u32      = 0;
-      let     coeff         : u32      = r.coeff;
+      let     r             : &mut Row<T> = &mut self.rows[row_idx];
+      let mut column_total  : T           = T::ZERO;
+      let mut max_sum       : T           = T::ZERO;
+      let     coeff         : T           = r.coeff;
 
       for i in 0..self.columns.len() {
-        r.selection[i].extra = 0;
-        let mut t: u32       = self.columns[i];
+        r.selection[i].extra = T::ZERO;
+        let mut t: T         = self.columns[i];
 
         column_total += t;
 
         if t >= coeff {
-          t /= coeff;
+          t = min(t / coeff, r.cell_max(i));
           max_sum += t;
           r.selection[i].max_extra = t;
         }
         else {
-          r.selection[i].max_extra = 0;
+          r.selection[i].max_extra = T::ZERO;
         }
       }
 
-      let min_size: u32 = max(
-        r.min_size,
-        ceiling_division(
-          column_total as i32 - r.max_leave,
-          coeff as i32
-        ) as u32
-      );
-      let max_size: u32 = min(
+      // If less than `min_leave` remains, no selection can leave enough for the remaining rows.
+      if column_total < r.min_leave {
+        return false;
+      }
+      let min_size: T = if column_total > r.max_leave {
+        max(r.min_size, ceiling_division(column_total - r.max_leave, coeff))
+      } else {
+        r.min_size
+      };
+      let max_size: T = min(
         min(
           max_sum,
           r.max_size
         ),
         floor_division(
-          column_total as i32 - r.min_leave,
-          coeff as i32
-        ) as u32
+          column_total - r.min_leave,
+          coeff
+        )
       );
 
       if min_size > max_size {
@@ -475,9 +2479,20 @@ impl DiophantineSystem {
 
       r.current_size = min_size;
       r.current_max_size = max_size;
+      if self.lex {
+        return r.multiset_lex(&mut self.columns, None, true);
+      }
+      if max_size == T::ZERO {
+        // The only selection takes nothing, and every `extra` is already zero.
+        self.stats.empty_rows += 1;
+        return true;
+      }
     }
     else {
-      let r: &mut Row = &mut self.rows[row_idx];
+      let r: &mut Row<T> = &mut self.rows[row_idx];
+      if self.lex {
+        return r.multiset_lex(&mut self.columns, None, false);
+      }
 
       if r.multiset_select(&mut self.columns, false) {
         return true;
@@ -486,11 +2501,38 @@ impl DiophantineSystem {
         return false;
       }
 
-      r.current_size += 1;
+      r.current_size += T::ONE;
     }
 
     // Always succeeds
-    return self.rows[row_idx].multiset_select(&mut self.columns, true);
+    self.rows[row_idx].multiset_select(&mut self.columns, true)
+  }
+
+
+  /// Solves a row whose `max_size` is zero, for which the only selection takes nothing, without building the multiset
+  /// of either case. The residual columns are left as they are, which in a complex system the rows after it can make
+  /// up, since the row's own solubility vector admitted them. Only the leave bounds are checked. Returns false when
+  /// asked for the next selection. Rows whose feasible range of sums only turns out to be zero once the columns are
+  /// known take the same shortcut in `solve_row_simple()` and `solve_row_complex()`.
+  #[inline]
+  fn solve_row_empty(&mut self, row_idx: usize, find_first: bool) -> bool {
+    if !find_first {
+      return false;
+    }
+    let column_total = self.columns.iter().fold(T::ZERO, |total, &column| total + column);
+    let row          = &mut self.rows[row_idx];
+    if column_total < row.min_leave || column_total > row.max_leave {
+      return false;
+    }
+    for s in row.selection.iter_mut() {
+      s.base      = T::ZERO;
+      s.extra     = T::ZERO;
+      s.max_extra = T::ZERO;
+    }
+    row.current_size     = T::ZERO;
+    row.current_max_size = T::ZERO;
+    self.stats.empty_rows += 1;
+    true
   }
 
 
@@ -498,106 +2540,163 @@ impl DiophantineSystem {
   fn solve_simple(&mut self, mut find_first: bool) -> bool {
     #[cfg(feature = "TRACE_CALLS")]
     println!("solve_simple");
-    if self.rows.len() > 1 {
-      let penultimate_idx = self.rows.len() - 2;
-      let mut i = if find_first { 0 } else { penultimate_idx };
-
-      loop {
-        find_first = self.solve_row_simple(i, find_first);
-        if find_first {
-          if i == penultimate_idx {
-            break;
+    loop {
+      let searched = if self.last_row_shortcut { self.rows.len() - 1 } else { self.rows.len() };
+      if searched > 0 {
+        let penultimate_idx = searched - 1; // the last row searched for a selection
+        let mut i = if find_first { 0 } else { penultimate_idx };
+
+        loop {
+          find_first = self.solve_row_simple(i, find_first);
+          // Skip selections with too many nonzero entries.
+          while find_first && !self.rows[i].respects_cell_count_max() {
+            find_first = self.solve_row_simple(i, false);
           }
-          i += 1;
-        }
-        else {
-          if i == 0 {
-            break;
+          #[cfg(feature = "debug_checks")]
+          invariant!(self, !find_first || self.columns_conserved(i + 1), "column values not conserved", false);
+          #[cfg(feature = "TRACE_CALLS")]
+          println!(
+            "solve_row_simple row_idx={} current_size={} success={}",
+            i, self.rows[i].current_size, find_first
+          );
+          if self.out_of_budget() || self.invariant_violated {
+            return false;
+          }
+          if find_first {
+            if i == penultimate_idx {
+              break;
+            }
+            i += 1;
+          }
+          else {
+            if i == 0 {
+              break;
+            }
+            i -= 1;
+            self.stats.backtracks += 1;
+            #[cfg(feature = "TRACE_CALLS")]
+            println!("backtrack row_idx={}", i);
           }
-          i -= 1;
         }
       }
-    }
 
-    if find_first {
-      self.solve_last_row_simple();
-    }
-    else {
-      self.failed = true;
+      if !self.last_row_shortcut {
+        // The last row's selection has taken what was left.
+        if !find_first {
+          self.failed = true;
+        }
+        return find_first;
+      }
+      if find_first && self.solve_last_row_simple() {
+        return true;
+      }
+      if !find_first || self.rows.len() == 1 {
+        self.failed = true;
+        return false;
+      }
+      // The last row violates a bound on one of its entries, so move on to the next selection of the other rows.
+      find_first = false;
     }
-
-    find_first
   }
   // endregion
 
 
   // region The Complex Case
 
+  /// Returns false if the last row's allocation violates a bound on an entry of the row or on the number of its nonzero
+  /// entries.
   #[inline]
-  fn solve_last_row_complex(&mut self) {
-    let last_row_idx  : usize                   = self.rows.len() - 1;
-    let r             : &mut Row                = &mut self.rows[last_row_idx];
-    let selection     : &mut Vec<crate::Select> = &mut r.selection;
-    let soluble       : &Vec<Soluble>           = &r.soluble;
-    let nr_columns    : usize                   = self.columns.len();
-
-    for i in 0..nr_columns {
-        let t = soluble[self.columns[i] as usize].min;
-        assert!(t != Soluble::INSOLUBLE, "solubility bug");
-        selection[i].extra = t as u32;
+  fn solve_last_row_complex(&mut self) -> bool {
+    let last_row_idx  : usize             = self.rows.len() - 1;
+    let r             : &mut Row<T>       = &mut self.rows[last_row_idx];
+    let soluble       : &SolubilityVector<T> = &r.soluble;
+
+    for (s, column) in r.selection.iter_mut().zip(self.columns.iter()) {
+        let t = soluble.min(column.to_usize());
+        invariant!(self, t != Soluble::INSOLUBLE, "solubility bug", false);
+        s.extra = t;
     }
+    r.respects_cell_max() && r.respects_cell_count_max()
   }
 
 
   fn solve_row_complex(&mut self, row_idx: usize, find_first: bool) -> bool {
     #[cfg(feature = "TRACE_CALLS")]
     println!("solve_row_complex");
+    self.stats.nodes_visited += 1;
+    if self.rows[row_idx].max_size == T::ZERO {
+      return self.solve_row_empty(row_idx, find_first);
+    }
     if find_first {
       if !self.viable(row_idx) {
         return false;
       }
 
-      let     row          : &mut Row = &mut self.rows[row_idx];
-      let     coeff        : u32      = row.coeff;
-      let mut column_total : i32    = 0;
-      let mut max_sum      : i32      = 0;
-      let mut min_sum      : i32      = 0;
+      let (lower, upper) = self.rows.split_at_mut(row_idx + 1);
+      let     row          : &mut Row<T>          = lower.last_mut().unwrap();
+      let     next_soluble : &SolubilityVector<T> = &upper.first().unwrap().soluble;
+      let     coeff        : T                    = row.coeff;
+      let mut column_total : T                    = T::ZERO;
+      let mut max_sum      : T                    = T::ZERO;
+      let mut min_sum      : T                    = T::ZERO;
 
       for i in 0..self.columns.len() {
-        let t   : usize = self.columns[i] as usize;
-        let min : i32   = row.soluble[t].min;
-        let max : i32   = row.soluble[t].max;
-        assert!(min != Soluble::INSOLUBLE, "min Soluble::INSOLUBLE");
-        assert!(max != Soluble::INSOLUBLE, "max Soluble::INSOLUBLE");
-        assert!(min <= max, "min > max");
-
-        row.selection[i].base      = min as u32;
-        row.selection[i].extra     = 0;
-        row.selection[i].max_extra = (max - min) as u32;
-
-        column_total += t as i32;
+        let     t   : T = self.columns[i];
+        let     min : T = row.soluble.min(t.to_usize());
+        let mut max : T = row.soluble.max(t.to_usize());
+        invariant!(self, min != Soluble::INSOLUBLE, "min Soluble::INSOLUBLE", false);
+        invariant!(self, max != Soluble::INSOLUBLE, "max Soluble::INSOLUBLE", false);
+        invariant!(self, min <= max, "min > max", false);
+        // `multiset_complex` takes the greatest amount whole, relying on it to leave a soluble value for the rows after
+        // this one, so a bound on the entry lowers it to the greatest amount within the bound that does.
+        if row.cell_max(i) < max {
+          max = row.cell_max(i);
+          while max > min && !next_soluble.is_soluble((t - max * coeff).to_usize()) {
+            max -= T::ONE;
+          }
+        }
+        if min > max {
+          // Even the least this row can take from column `i` exceeds the bound on the entry.
+          return false;
+        }
+
+        row.selection[i].base      = min;
+        row.selection[i].extra     = T::ZERO;
+        row.selection[i].max_extra = max - min;
+
+        column_total += t;
         min_sum      += min;
         max_sum      += max;
       }
 
-      let min_size = max(
-        max(min_sum, row.min_size as i32),
-        ceiling_division((column_total - row.max_leave) as i32, coeff as i32),
-      );
+      // If less than `min_leave` remains, no selection can leave enough for the remaining rows.
+      if column_total < row.min_leave {
+        return false;
+      }
+      let mut min_size = max(min_sum, row.min_size);
+      if column_total > row.max_leave {
+        min_size = max(min_size, ceiling_division(column_total - row.max_leave, coeff));
+      }
       let max_size = min(
-        min(max_sum, row.max_size as i32),
-        floor_division((column_total - row.min_leave) as i32, coeff as i32),
+        min(max_sum, row.max_size),
+        floor_division(column_total - row.min_leave, coeff),
       );
 
       if min_size > max_size {
         return false;
       }
 
-      row.current_size     = (min_size - min_sum) as u32; // The maxes above gaurantee this is positive.
-      row.current_max_size = (max_size - min_sum) as u32; // The mins  above gaurantee this is positive.
+      // `min_sum <= min_size <= max_size` by the maxes and the test above, so neither subtraction underflows.
+      row.current_size     = min_size - min_sum;
+      row.current_max_size = max_size - min_sum;
+      if max_size == T::ZERO && !self.lex {
+        // The only selection takes nothing: every `base` and `extra` is already zero.
+        self.stats.empty_rows += 1;
+        return true;
+      }
 
       for i in 0..self.columns.len() {
-        if row.selection[i].base > 0 {
+        if row.selection[i].base > T::ZERO {
           self.columns[i] -= row.selection[i].base * coeff;
           // assert!(self.columns[i] >= 0, "value -ve");
         }
@@ -606,34 +2705,36 @@ impl DiophantineSystem {
 
     // Get mutable access to two elements at once.
     let (lower, upper) = self.rows.split_at_mut(row_idx + 1);
-    let row          : &mut Row          = lower.last_mut().unwrap();              // self.rows[row_idx];
-    let coeff        : u32               = row.coeff;
-    let next_soluble : &mut Vec<Soluble> = &mut upper.first_mut().unwrap().soluble; // self.rows[row_idx + 1].soluble;
+    let row          : &mut Row<T>          = lower.last_mut().unwrap();              // self.rows[row_idx];
+    let coeff        : T                    = row.coeff;
+    let next_soluble : &SolubilityVector<T> = &upper.first().unwrap().soluble; // self.rows[row_idx + 1].soluble;
 
+    if self.lex {
+      if row.multiset_lex(&mut self.columns, Some(next_soluble), find_first) {
+        return true;
+      }
+    }
     // This is an else for the previous if, but we want the bindings r and next_soluble in the outer scope.
-    if !find_first {
+    else if !find_first {
       if row.multiset_complex(&mut self.columns, next_soluble, false) {
         return true;
       }
 
-      row.current_size += 1;
+      row.current_size += T::ONE;
     }
 
-    while row.current_size <= row.current_max_size {
+    while !self.lex && row.current_size <= row.current_max_size {
       if row.multiset_complex(&mut self.columns, next_soluble, true) {
         return true;
       }
 
-      row.current_size += 1;
+      row.current_size += T::ONE;
     }
 
     for i in 0..self.columns.len() {
-      if (&mut row.selection)[i].base > 0 {
+      if (&mut row.selection)[i].base > T::ZERO {
         self.columns[i] += row.selection[i].base * coeff;
-        assert!(
-          self.columns[i] <= self.max_column_value,
-          "value too big"
-        );
+        invariant!(self, self.columns[i] <= self.max_column_value, "value too big", false);
       }
     }
 
@@ -646,30 +2747,72 @@ impl DiophantineSystem {
   fn solve_complex(&mut self, mut find_first: bool) -> bool {
     #[cfg(feature = "TRACE_CALLS")]
     println!("solve_complex");
-    if self.rows.len() > 1 {
-      let penultimate = self.rows.len() - 2;
-      let mut i = if find_first { 0 } else { penultimate };
-      loop {
-        find_first = self.solve_row_complex(i, find_first);
-        if find_first {
-          if i == penultimate {
-            break;
+    loop {
+      let searched = if self.last_row_shortcut { self.rows.len() - 1 } else { self.rows.len() };
+      if searched > 0 {
+        let penultimate = searched - 1; // the last row searched for a selection
+        let mut i = if find_first { 0 } else { penultimate };
+        loop {
+          let mut first = find_first;
+          find_first = loop {
+            let found = if i + 1 == self.rows.len() {
+              // The last row has no rows after it to need solubility vectors for.
+              self.solve_row_simple(i, first)
+            } else {
+              self.solve_row_complex(i, first)
+            };
+            // Skip selections with too many nonzero entries.
+            if !found || self.rows[i].respects_cell_count_max() {
+              break found;
+            }
+            first = false;
+          };
+          #[cfg(feature = "debug_checks")]
+          invariant!(self, !find_first || self.columns_conserved(i + 1), "column values not conserved", false);
+          #[cfg(feature = "TRACE_CALLS")]
+          println!(
+            "solve_row_complex row_idx={} current_size={} success={}",
+            i, self.rows[i].current_size, find_first
+          );
+          if self.out_of_budget() || self.invariant_violated {
+            return false;
           }
-          i += 1;
-        } else {
-          if i == 0 {
-            break;
+          if find_first {
+            if i == penultimate {
+              break;
+            }
+            i += 1;
+          } else {
+            if i == 0 {
+              break;
+            }
+            i -= 1;
+            self.stats.backtracks += 1;
+            #[cfg(feature = "TRACE_CALLS")]
+            println!("backtrack row_idx={}", i);
           }
-          i -= 1;
         }
       }
+      if !self.last_row_shortcut {
+        // The last row's selection has taken what was left.
+        if !find_first {
+          self.failed = true;
+        }
+        return find_first;
+      }
+      if find_first && self.solve_last_row_complex() {
+        return true;
+      }
+      if self.invariant_violated {
+        return false;
+      }
+      if !find_first || self.rows.len() == 1 {
+        self.failed = true;
+        return false;
+      }
+      // The last row violates a bound on one of its entries, so move on to the next selection of the other rows.
+      find_first = false;
     }
-    if find_first {
-      self.solve_last_row_complex();
-    } else {
-      self.failed = true;
-    }
-    find_first
   }
 
   // endregion
@@ -677,3 +2820,81 @@ impl DiophantineSystem {
 }
 
 
+/// The largest row sum `count_selections()` counts the selections of exactly, bounding the memory and time it takes.
+const MAX_COUNTED_SUM: usize = 1 << 16;
+
+/// The number of ways to choose `x[c]` in `0..=bounds[c]` for every `c` with `low <= sum(x) <= high`, saturating at
+/// `u64::MAX`. Beyond a `high` of `MAX_COUNTED_SUM`, the number of ways to choose the entries within their bounds and
+/// `high` without regard to the sum is returned instead, which is at least as large.
+fn count_selections<T: DiophantineInt>(bounds: &[T], low: T, high: T) -> u64 {
+  let total = bounds.iter().fold(T::ZERO, |total, &bound| total.saturating_add(bound));
+  let high  = min(high, total);
+  if low > high {
+    return 0;
+  }
+  if high > T::from_usize(MAX_COUNTED_SUM) {
+    return bounds.iter().fold(1u64, |product, &bound| {
+      let choices = min(bound, high).to_i64().map_or(u64::MAX, |bound| bound as u64 + 1);
+      product.saturating_mul(choices)
+    });
+  }
+  let (low, high) = (low.to_usize(), high.to_usize());
+
+  // ways[s] is the number of ways to choose the entries so far with sum s.
+  let mut ways = vec![0u64; high + 1];
+  ways[0] = 1;
+  for &bound in bounds.iter() {
+    let bound    = min(bound, T::from_usize(high)).to_usize();
+    let previous = ways.clone();
+    // The sum of previous[s - bound ..= s]. Counts are at most u64::MAX, so this cannot overflow.
+    let mut window: u128 = 0;
+    for s in 0..=high {
+      window += previous[s] as u128;
+      if s > bound {
+        window -= previous[s - bound - 1] as u128;
+      }
+      ways[s] = min(window, u64::MAX as u128) as u64;
+    }
+  }
+  ways[low..=high].iter().fold(0u64, |count, &w| count.saturating_add(w))
+}
+
+
+/// Adds rows `(coeff, min_size, max_size)` in order as if by `insert_row`. Panics if a row is rejected; use
+/// `insert_row` to handle the error instead.
+impl<T: DiophantineInt> Extend<(T, T, T)> for DiophantineSystem<T> {
+  fn extend<I: IntoIterator<Item = (T, T, T)>>(&mut self, rows: I) {
+    for (coeff, min_size, max_size) in rows {
+      if let Err(error) = self.insert_row(coeff, min_size, max_size) {
+        panic!("cannot insert row ({}, {}, {}): {}", coeff, min_size, max_size, error);
+      }
+    }
+  }
+}
+
+
+impl<T: DiophantineInt> Debug for DiophantineSystem<T> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    writeln!(f, "DiophantineSystem {{")?;
+    writeln!(f, "  closed: {}, complex: {}, failed: {}", self.closed, self.complex, self.failed)?;
+    writeln!(f, "  columns: {:?}", self.original_columns)?;
+    if self.closed {
+      // Rows are in sorted order, with the detail of the search state.
+      writeln!(f, "  residual columns: {:?}", self.columns)?;
+      writeln!(f, "  row permute: {:?}", self.row_permute)?;
+      if !self.column_permute.is_empty() {
+        writeln!(f, "  column permute: {:?}", self.column_permute)?;
+      }
+      writeln!(f, "  rows:")?;
+      for row in self.rows.iter() {
+        write!(f, "{}", row)?;
+      }
+    } else {
+      writeln!(f, "  rows:")?;
+      for row in self.rows.iter() {
+        writeln!(f, "\tcoeff: {}, minSize: {}, maxSize: {}", row.coeff, row.min_size, row.max_size)?;
+      }
+    }
+    write!(f, "}}")
+  }
+}